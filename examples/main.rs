@@ -1,11 +1,14 @@
 use msbuild::MsBuild;
+use std::path::Path;
 
 fn main() {
     let mb = MsBuild::find_msbuild(Some("2017"));
     match mb {
-        Ok(mut msb) => {
-            msb.run("./".into(), &[]);
+        Ok(msb) => {
             println!("Found msbuild");
+            if let Err(e) = msb.run(Path::new("./"), &[]) {
+                println!("Failed to run msbuild: {}", e);
+            }
         }
         Err(_) => {
             println!("Failed to find msbuild");
@@ -0,0 +1,276 @@
+//! Module containing the architectures that the
+//! Visual Studio tools can run on and target.
+use crate::error::{Error, Result};
+use std::{convert::TryFrom, fmt::Display};
+
+/// Enum holding the architectures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Arch {
+    X86,
+    X64,
+    Arm,
+    Arm64,
+    /// ARM64 binaries that are compatible with x64 code. The tools
+    /// and libraries are the ones of [`Arch::Arm64`].
+    Arm64ec,
+}
+
+impl Arch {
+    /// The name of the architecture e.g. `arm64ec`.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::X86 => "x86",
+            Self::X64 => "x64",
+            Self::Arm => "arm",
+            Self::Arm64 => "arm64",
+            Self::Arm64ec => "arm64ec",
+        }
+    }
+
+    /// The name of the architecture as used in the directory
+    /// names of the VS installation and the Windows SDK e.g. `x64`.
+    pub const fn dir_name(&self) -> &'static str {
+        match self {
+            Self::Arm64ec => Self::Arm64.dir_name(),
+            arch => arch.name(),
+        }
+    }
+
+    /// The name of the directory containing the tools that
+    /// run on the architecture e.g. `Hostx64`.
+    pub const fn host_dir_name(&self) -> &'static str {
+        match self {
+            Self::X86 => "Hostx86",
+            Self::X64 => "Hostx64",
+            Self::Arm => "Hostarm",
+            Self::Arm64 | Self::Arm64ec => "Hostarm64",
+        }
+    }
+
+    /// The value of the msbuild `Platform` property e.g. `Win32`
+    /// for x86.
+    pub const fn msbuild_platform(&self) -> &'static str {
+        match self {
+            Self::X86 => "Win32",
+            Self::X64 => "x64",
+            Self::Arm => "ARM",
+            Self::Arm64 => "ARM64",
+            Self::Arm64ec => "ARM64EC",
+        }
+    }
+
+    /// The architecture of a Rust target triple e.g. `Arm64` for
+    /// `aarch64-pc-windows-msvc`. Only the architecture part of the
+    /// triple is interpreted.
+    pub fn from_target_triple(triple: &str) -> Result<Self> {
+        let arch = triple.split('-').next().unwrap_or_default();
+        match arch {
+            "i386" | "i586" | "i686" => Ok(Self::X86),
+            "x86_64" => Ok(Self::X64),
+            "aarch64" => Ok(Self::Arm64),
+            "arm64ec" => Ok(Self::Arm64ec),
+            arch if arch.starts_with("thumbv7") || arch.starts_with("armv7") => Ok(Self::Arm),
+            _ => Err(Error::UnknownArch(triple.to_string())),
+        }
+    }
+
+    /// The architecture the current process was compiled for, if it
+    /// is one of the architectures.
+    pub const fn current() -> Option<Self> {
+        if cfg!(target_arch = "x86") {
+            Some(Self::X86)
+        } else if cfg!(target_arch = "x86_64") {
+            Some(Self::X64)
+        } else if cfg!(target_arch = "arm") {
+            Some(Self::Arm)
+        } else if cfg!(target_arch = "aarch64") {
+            Some(Self::Arm64)
+        } else if cfg!(target_arch = "arm64ec") {
+            Some(Self::Arm64ec)
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Arch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl TryFrom<&str> for Arch {
+    type Error = Error;
+
+    /// Parses the name of the architecture, the names used by
+    /// the VS scripts, e.g. `amd64`, and the msbuild platforms
+    /// are also accepted.
+    fn try_from(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "x86" | "win32" => Ok(Self::X86),
+            "x64" | "amd64" => Ok(Self::X64),
+            "arm" => Ok(Self::Arm),
+            "arm64" => Ok(Self::Arm64),
+            "arm64ec" => Ok(Self::Arm64ec),
+            _ => Err(Error::UnknownArch(s.to_string())),
+        }
+    }
+}
+
+/// Type holding the architecture the tools run on, the host,
+/// and the architecture of the binaries they produce, the target.
+///
+/// # Examples
+///
+/// ```
+/// use msbuild::{Arch, HostTarget};
+///
+/// let host_target = HostTarget::new(Arch::X64, Arch::Arm64);
+/// assert!(host_target.is_cross());
+/// assert_eq!(host_target.to_string(), "x64_arm64");
+///
+/// // In a build script the target is read from the `TARGET`
+/// // environment variable set by cargo.
+/// let host_target = HostTarget::from_target_triple("aarch64-pc-windows-msvc")
+///     .expect("The triple should be known");
+/// assert_eq!(host_target.target(), Arch::Arm64);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HostTarget {
+    host: Arch,
+    target: Arch,
+}
+
+impl HostTarget {
+    /// Creates a HostTarget object for the tools running on the host
+    /// and producing binaries for the target.
+    pub const fn new(host: Arch, target: Arch) -> Self {
+        Self { host, target }
+    }
+
+    /// Creates a HostTarget object for the tools running on, and
+    /// producing binaries for, the architecture.
+    pub const fn native(arch: Arch) -> Self {
+        Self::new(arch, arch)
+    }
+
+    /// Creates a HostTarget object for the tools running on the
+    /// current architecture, or x64 if the current architecture is
+    /// unknown, and producing binaries for the Rust target triple.
+    pub fn from_target_triple(triple: &str) -> Result<Self> {
+        Arch::from_target_triple(triple)
+            .map(|target| Self::new(Arch::current().unwrap_or(Arch::X64), target))
+    }
+
+    /// The architecture the tools run on.
+    pub const fn host(&self) -> Arch {
+        self.host
+    }
+
+    /// The architecture of the binaries the tools produce.
+    pub const fn target(&self) -> Arch {
+        self.target
+    }
+
+    /// Whether or not the tools produce binaries for another
+    /// architecture than they run on.
+    pub fn is_cross(&self) -> bool {
+        self.host.dir_name() != self.target.dir_name()
+    }
+}
+
+impl Display for HostTarget {
+    /// Formats the pair the way `vcvarsall.bat` expects it
+    /// e.g. `x64` or `x64_arm64`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_cross() {
+            write!(f, "{}_{}", self.host.dir_name(), self.target.dir_name())
+        } else {
+            f.write_str(self.target.dir_name())
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_try_from() {
+        [
+            ("x86", Arch::X86),
+            ("Win32", Arch::X86),
+            ("x64", Arch::X64),
+            ("AMD64", Arch::X64),
+            ("arm", Arch::Arm),
+            ("ARM64", Arch::Arm64),
+            ("ARM64EC", Arch::Arm64ec),
+        ]
+        .iter()
+        .for_each(|(s, expected)| {
+            assert_eq!(
+                Arch::try_from(*s).expect("It should be possible to parse the architecture."),
+                *expected
+            )
+        });
+        assert!(matches!(Arch::try_from("mips"), Err(Error::UnknownArch(_))));
+    }
+
+    #[test]
+    fn test_from_target_triple() {
+        [
+            ("i686-pc-windows-msvc", Arch::X86),
+            ("i586-pc-windows-msvc", Arch::X86),
+            ("x86_64-pc-windows-msvc", Arch::X64),
+            ("x86_64-uwp-windows-msvc", Arch::X64),
+            ("thumbv7a-pc-windows-msvc", Arch::Arm),
+            ("aarch64-pc-windows-msvc", Arch::Arm64),
+            ("arm64ec-pc-windows-msvc", Arch::Arm64ec),
+        ]
+        .iter()
+        .for_each(|(triple, expected)| {
+            assert_eq!(
+                Arch::from_target_triple(triple)
+                    .expect("It should be possible to parse the target triple."),
+                *expected,
+                "{}",
+                triple
+            )
+        });
+        assert!(matches!(
+            Arch::from_target_triple("riscv64gc-unknown-linux-gnu"),
+            Err(Error::UnknownArch(_))
+        ));
+        assert!(Arch::from_target_triple("").is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Arch::X64.to_string(), "x64");
+        assert_eq!(Arch::Arm64ec.to_string(), "arm64ec");
+        assert_eq!(Arch::Arm64ec.dir_name(), "arm64");
+        assert_eq!(Arch::Arm64ec.host_dir_name(), "Hostarm64");
+        assert_eq!(Arch::X86.msbuild_platform(), "Win32");
+        assert_eq!(Arch::Arm64ec.msbuild_platform(), "ARM64EC");
+    }
+
+    #[test]
+    fn test_host_target() {
+        assert_eq!(HostTarget::native(Arch::X86).to_string(), "x86");
+        assert!(!HostTarget::new(Arch::Arm64, Arch::Arm64ec).is_cross());
+        assert_eq!(
+            HostTarget::new(Arch::Arm64, Arch::Arm64ec).to_string(),
+            "arm64"
+        );
+        assert_eq!(HostTarget::new(Arch::X86, Arch::X64).to_string(), "x86_x64");
+        assert_eq!(
+            HostTarget::from_target_triple("x86_64-pc-windows-msvc")
+                .expect("It should be possible to parse the target triple.")
+                .target(),
+            Arch::X64
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Module containing the result of a msbuild execution.
+use crate::diagnostics::{Diagnostic, Severity};
+use std::{process::ExitStatus, time::Duration};
+
+/// The outcome of running msbuild.
+#[derive(Debug, Clone)]
+pub struct BuildOutcome {
+    status: ExitStatus,
+    stdout: String,
+    stderr: String,
+    duration: Duration,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl BuildOutcome {
+    /// Creates a new outcome from the captured output of
+    /// the process.
+    pub(crate) fn new(
+        status: ExitStatus,
+        stdout: String,
+        stderr: String,
+        duration: Duration,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Self {
+        Self {
+            status,
+            stdout,
+            stderr,
+            duration,
+            diagnostics,
+        }
+    }
+
+    /// The exit status of the msbuild process.
+    pub fn status(&self) -> ExitStatus {
+        self.status
+    }
+
+    /// Whether or not the build was successful.
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// The exit code of the msbuild process if it
+    /// was not terminated by a signal.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.status.code()
+    }
+
+    /// The captured stdout of the msbuild process. This is empty
+    /// if the output was streamed.
+    pub fn stdout(&self) -> &str {
+        self.stdout.as_str()
+    }
+
+    /// The captured stderr of the msbuild process. This is empty
+    /// if the output was streamed.
+    pub fn stderr(&self) -> &str {
+        self.stderr.as_str()
+    }
+
+    /// The time it took for the msbuild process to finish.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// All the diagnostics found in the output.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.diagnostics.as_slice()
+    }
+
+    /// The diagnostics with error severity.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity() == Severity::Error)
+    }
+
+    /// The diagnostics with warning severity.
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity() == Severity::Warning)
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::diagnostics;
+
+    fn exit_status(code: i32) -> ExitStatus {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            ExitStatus::from_raw(code << 8)
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::ExitStatusExt;
+            ExitStatus::from_raw(code as u32)
+        }
+    }
+
+    #[test]
+    fn test_build_outcome_failure() {
+        let stdout = "\
+main.cpp(3,1): error C2143: syntax error: missing ';' before '}' [app.vcxproj]
+main.cpp(1,10): warning C4100: 'argc': unreferenced formal parameter [app.vcxproj]
+"
+        .to_string();
+        let diagnostics = diagnostics::parse(stdout.as_str());
+        let outcome = BuildOutcome::new(
+            exit_status(1),
+            stdout,
+            "stderr".to_string(),
+            Duration::from_secs(2),
+            diagnostics,
+        );
+        assert!(!outcome.success());
+        assert_eq!(outcome.exit_code(), Some(1));
+        assert_eq!(outcome.stderr(), "stderr");
+        assert_eq!(outcome.duration(), Duration::from_secs(2));
+        assert_eq!(outcome.diagnostics().len(), 2);
+        assert_eq!(outcome.errors().count(), 1);
+        assert_eq!(outcome.warnings().count(), 1);
+
+        let error = crate::Error::BuildFailed(Box::new(outcome));
+        assert_eq!(error.to_string(), "Failed to run msbuild: Exit code [1]");
+        let extracted = error
+            .build_outcome()
+            .expect("It should be possible to extract the outcome from the error.");
+        assert_eq!(extracted.exit_code(), Some(1));
+    }
+}
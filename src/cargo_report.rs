@@ -0,0 +1,186 @@
+//! Module for forwarding the result of a msbuild execution to cargo
+//! when msbuild is executed from a build script.
+//!
+//! Cargo only shows the output of a build script when it fails, but
+//! lines starting with `cargo:warning=` are always shown.
+use crate::{
+    build_outcome::BuildOutcome,
+    diagnostics::{Diagnostic, Severity},
+};
+use std::path::{Path, PathBuf};
+
+/// The cargo directives for a msbuild execution.
+#[derive(Debug, Clone)]
+pub struct CargoReport {
+    warnings: Vec<String>,
+    rerun_if_changed: Vec<PathBuf>,
+}
+
+impl CargoReport {
+    /// Creates a report containing a `cargo:warning` for each diagnostic
+    /// in the outcome and a `cargo:rerun-if-changed` for each project
+    /// the diagnostics refer to.
+    pub fn new(outcome: &BuildOutcome) -> Self {
+        let mut report = Self {
+            warnings: outcome
+                .diagnostics()
+                .iter()
+                .map(Self::format_diagnostic)
+                .collect(),
+            rerun_if_changed: Vec::new(),
+        };
+        outcome
+            .diagnostics()
+            .iter()
+            .filter_map(|d| d.project())
+            .for_each(|p| report.add_project(p));
+        report
+    }
+
+    /// Adds a project file that cargo should watch for changes.
+    pub fn with_project(mut self, project: &Path) -> Self {
+        self.add_project(project);
+        self
+    }
+
+    /// The lines that should be printed to stdout by the build script.
+    pub fn directives(&self) -> Vec<String> {
+        self.warnings
+            .iter()
+            .map(|w| format!("cargo:warning={}", w))
+            .chain(
+                self.rerun_if_changed
+                    .iter()
+                    .map(|p| format!("cargo:rerun-if-changed={}", p.display())),
+            )
+            .collect()
+    }
+
+    /// Prints the directives to stdout where cargo will pick them up.
+    pub fn emit(&self) {
+        self.directives().iter().for_each(|d| println!("{}", d));
+    }
+
+    /// The `cargo:rerun-if-env-changed` lines for the environment
+    /// variables that are read by the crate, see [`crate::ENV_VARS`].
+    pub fn env_directives() -> Vec<String> {
+        crate::ENV_VARS
+            .iter()
+            .map(|key| format!("cargo:rerun-if-env-changed={}", key))
+            .collect()
+    }
+
+    /// Prints the `cargo:rerun-if-env-changed` lines to stdout so cargo
+    /// reruns the build script when one of the environment variables
+    /// that are read by the crate changes.
+    pub fn emit_env_directives() {
+        Self::env_directives()
+            .iter()
+            .for_each(|d| println!("{}", d));
+    }
+
+    fn add_project(&mut self, project: &Path) {
+        if !self.rerun_if_changed.iter().any(|p| p == project) {
+            self.rerun_if_changed.push(project.to_path_buf());
+        }
+    }
+
+    /// Formats the diagnostic as `file:line:col: category code: message`.
+    fn format_diagnostic(diagnostic: &Diagnostic) -> String {
+        let mut location = diagnostic
+            .file()
+            .map(|f| f.display().to_string())
+            .or_else(|| diagnostic.origin().map(str::to_string))
+            .unwrap_or_default();
+        if let Some(line) = diagnostic.line() {
+            location = format!("{}:{}", location, line);
+            if let Some(column) = diagnostic.column() {
+                location = format!("{}:{}", location, column);
+            }
+        }
+        let category = match diagnostic.severity() {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let header = match diagnostic.code() {
+            Some(code) => format!("{} {}", category, code),
+            None => category.to_string(),
+        };
+        if location.is_empty() {
+            format!("{}: {}", header, diagnostic.message())
+        } else {
+            format!("{}: {}: {}", location, header, diagnostic.message())
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cargo_report_format_diagnostic() {
+        [
+            (
+                "C:\\src\\main.cpp(12,5): error C2065: 'x': undeclared identifier [app.vcxproj]",
+                "C:\\src\\main.cpp:12:5: error C2065: 'x': undeclared identifier",
+            ),
+            (
+                "main.cpp(7): warning : unused",
+                "main.cpp:7: warning: unused",
+            ),
+            (
+                "LINK : fatal error LNK1120: 1 unresolved externals",
+                "LINK: error LNK1120: 1 unresolved externals",
+            ),
+            (
+                "error MSB4025: The project file could not be loaded.",
+                "error MSB4025: The project file could not be loaded.",
+            ),
+        ]
+        .iter()
+        .for_each(|(line, expected)| {
+            let diagnostic = Diagnostic::parse_line(line)
+                .expect("It should be possible to parse the diagnostic.");
+            assert_eq!(CargoReport::format_diagnostic(&diagnostic), *expected);
+        });
+    }
+
+    #[test]
+    fn test_cargo_report_directives() {
+        let report = CargoReport {
+            warnings: vec!["main.cpp:1: warning: unused".to_string()],
+            rerun_if_changed: vec![PathBuf::from("app.vcxproj")],
+        }
+        .with_project(Path::new("app.vcxproj"))
+        .with_project(Path::new("app.sln"));
+        assert_eq!(
+            report.directives(),
+            [
+                "cargo:warning=main.cpp:1: warning: unused",
+                "cargo:rerun-if-changed=app.vcxproj",
+                "cargo:rerun-if-changed=app.sln",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cargo_report_env_directives() {
+        let directives = CargoReport::env_directives();
+        assert_eq!(directives.len(), crate::ENV_VARS.len());
+        [
+            "VS_WHERE_PATH",
+            "VS_WHERE_JSON",
+            "VS_INSTALLATION_PATH",
+            "VS_INSTANCE_ID",
+            "WIN_SDK_PATH",
+        ]
+        .iter()
+        .for_each(|key| {
+            assert!(directives.contains(&format!("cargo:rerun-if-env-changed={}", key)))
+        });
+    }
+}
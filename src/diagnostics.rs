@@ -0,0 +1,404 @@
+//! Module containing code for parsing the diagnostics
+//! (errors and warnings) that msbuild writes to its output.
+//!
+//! msbuild and the tools it invokes report diagnostics using the
+//! canonical format:
+//!
+//! `Origin : [Subcategory] Category [Code] : Text [Project]`
+//!
+//! - The origin is optional and is either a file with an optional
+//!   location e.g. `main.cpp(12,5)` or the name of a tool e.g. `LINK`.
+//! - The location can be `(line)`, `(line-line)`, `(line,col)`,
+//!   `(line,col-col)` or `(line,col,line,col)`.
+//! - The subcategory is optional free text e.g. `fatal`.
+//! - The category is either `error` or `warning`.
+//! - The project is appended by msbuild and is optional.
+use std::path::{Path, PathBuf};
+
+/// The severity of a diagnostic.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    /// Parses the category part of a diagnostic.
+    fn parse(value: &str) -> Option<Self> {
+        if value.eq_ignore_ascii_case("error") {
+            Some(Self::Error)
+        } else if value.eq_ignore_ascii_case("warning") {
+            Some(Self::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+/// A diagnostic emitted by msbuild or one of the tools
+/// it invoked.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    severity: Severity,
+    subcategory: Option<String>,
+    code: Option<String>,
+    origin: Option<String>,
+    file: Option<PathBuf>,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+    message: String,
+    project: Option<PathBuf>,
+}
+
+impl Diagnostic {
+    const CATEGORIES: [&'static str; 2] = ["error", "warning"];
+
+    /// The severity of the diagnostic.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// The subcategory of the diagnostic e.g. `fatal`.
+    pub fn subcategory(&self) -> Option<&str> {
+        self.subcategory.as_deref()
+    }
+
+    /// The code of the diagnostic e.g. `C2065`.
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// The origin of the diagnostic, without the location, as it
+    /// appeared in the output. This is either a file or the name of
+    /// the tool that emitted the diagnostic e.g. `LINK`.
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// The file the diagnostic refers to.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// The line in the file the diagnostic refers to.
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// The column in the file the diagnostic refers to.
+    pub fn column(&self) -> Option<u32> {
+        self.column
+    }
+
+    /// The last line of the range the diagnostic refers to.
+    pub fn end_line(&self) -> Option<u32> {
+        self.end_line
+    }
+
+    /// The last column of the range the diagnostic refers to.
+    pub fn end_column(&self) -> Option<u32> {
+        self.end_column
+    }
+
+    /// The message of the diagnostic.
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /// The project that was being built when the
+    /// diagnostic was emitted.
+    pub fn project(&self) -> Option<&Path> {
+        self.project.as_deref()
+    }
+
+    /// Parses a single line of msbuild output. Returns `None` if the
+    /// line is not a diagnostic in the canonical format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use msbuild::diagnostics::{Diagnostic, Severity};
+    ///
+    /// let diagnostic = Diagnostic::parse_line(
+    ///     "main.cpp(12,5): error C2065: 'x': undeclared identifier [app.vcxproj]",
+    /// )
+    /// .expect("The line is a canonical diagnostic");
+    ///
+    /// assert_eq!(diagnostic.severity(), Severity::Error);
+    /// assert_eq!(diagnostic.code(), Some("C2065"));
+    /// assert_eq!(diagnostic.line(), Some(12));
+    /// ```
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let line = strip_node_prefix(line.trim());
+        let (line, project) = split_project(line);
+        Self::CATEGORIES
+            .iter()
+            .flat_map(|category| {
+                find_words(line, category).map(move |index| (index, category.len()))
+            })
+            .collect::<std::collections::BTreeSet<(usize, usize)>>()
+            .into_iter()
+            .find_map(|(index, len)| Self::parse_at(line, index, len))
+            .map(|mut diagnostic| {
+                diagnostic.project = project.map(PathBuf::from);
+                diagnostic
+            })
+    }
+
+    // Internal function that tries to parse the line assuming that the
+    // category is found at the given index.
+    fn parse_at(line: &str, index: usize, len: usize) -> Option<Self> {
+        let severity = Severity::parse(&line[index..index + len])?;
+        let (code, message) = parse_code_and_message(&line[index + len..])?;
+
+        let prefix = &line[..index];
+        let (origin, subcategory) = match prefix.rfind(':') {
+            Some(colon) => (prefix[..colon].trim(), prefix[colon + 1..].trim()),
+            None => ("", prefix.trim()),
+        };
+        if !is_valid_subcategory(subcategory) || (origin.is_empty() && !subcategory.is_empty()) {
+            return None;
+        }
+
+        let mut diagnostic = Self {
+            severity,
+            subcategory: non_empty(subcategory),
+            code: non_empty(code),
+            origin: None,
+            file: None,
+            line: None,
+            column: None,
+            end_line: None,
+            end_column: None,
+            message: message.to_string(),
+            project: None,
+        };
+        if !origin.is_empty() {
+            diagnostic.parse_origin(origin);
+        }
+        Some(diagnostic)
+    }
+
+    // Internal function for parsing the origin into either a file
+    // with location or a tool name.
+    fn parse_origin(&mut self, origin: &str) {
+        let location = origin
+            .strip_suffix(')')
+            .and_then(|o| o.rsplit_once('('))
+            .and_then(|(file, location)| {
+                parse_location(location).map(|location| (file.trim_end(), location))
+            });
+        let file = match location {
+            Some((file, (line, column, end_line, end_column))) => {
+                self.line = Some(line);
+                self.column = column;
+                self.end_line = end_line;
+                self.end_column = end_column;
+                file
+            }
+            None => origin,
+        };
+        self.origin = Some(file.to_string());
+        if is_file_like(file) {
+            self.file = Some(PathBuf::from(file));
+        }
+    }
+}
+
+/// Parses all the diagnostics found in the msbuild output.
+///
+/// msbuild repeats all the diagnostics in a summary at the end of
+/// the build so duplicates are removed.
+pub fn parse(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    output
+        .lines()
+        .filter_map(Diagnostic::parse_line)
+        .for_each(|d| push_unique(&mut diagnostics, d));
+    diagnostics
+}
+
+/// Adds the diagnostic unless it already has been added.
+pub(crate) fn push_unique(diagnostics: &mut Vec<Diagnostic>, diagnostic: Diagnostic) {
+    if !diagnostics.contains(&diagnostic) {
+        diagnostics.push(diagnostic);
+    }
+}
+
+/// Removes the `1>` or `12:3>` prefix that msbuild adds to each
+/// line when building with multiple nodes.
+fn strip_node_prefix(line: &str) -> &str {
+    line.split_once('>')
+        .filter(|(prefix, _)| {
+            !prefix.is_empty()
+                && prefix
+                    .split(':')
+                    .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map_or(line, |(_, rest)| rest)
+}
+
+/// Splits the trailing ` [project]` from the line.
+fn split_project(line: &str) -> (&str, Option<&str>) {
+    line.strip_suffix(']')
+        .and_then(|l| l.rsplit_once(" ["))
+        .map_or((line, None), |(line, project)| (line, Some(project)))
+}
+
+/// Finds all the indices where the word is found as a
+/// separate word in the line, ignoring case.
+fn find_words<'a>(line: &'a str, word: &'a str) -> impl Iterator<Item = usize> + 'a {
+    let bytes = line.as_bytes();
+    (0..line.len())
+        .filter(move |&i| {
+            bytes[i..]
+                .get(..word.len())
+                .is_some_and(|candidate| candidate.eq_ignore_ascii_case(word.as_bytes()))
+        })
+        .filter(move |&i| i == 0 || bytes[i - 1].is_ascii_whitespace() || bytes[i - 1] == b':')
+        .filter(move |&i| matches!(bytes.get(i + word.len()), Some(b' ') | Some(b':')))
+}
+
+/// Parses the ` CODE: message` part that follows the category.
+fn parse_code_and_message(rest: &str) -> Option<(&str, &str)> {
+    let (code, message) = rest.split_once(':')?;
+    let code = code.trim();
+    if !code
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+    {
+        return None;
+    }
+    Some((code, message.trim()))
+}
+
+/// A location as line, column, end line and end column.
+type Location = (u32, Option<u32>, Option<u32>, Option<u32>);
+
+/// Parses the location inside the parenthesis of the origin.
+fn parse_location(location: &str) -> Option<Location> {
+    let parts = location.split(',').map(|p| p.trim()).collect::<Vec<&str>>();
+    match parts.as_slice() {
+        [line] => match line.split_once('-') {
+            Some((line, end_line)) => {
+                Some((line.parse().ok()?, None, Some(end_line.parse().ok()?), None))
+            }
+            None => Some((line.parse().ok()?, None, None, None)),
+        },
+        [line, column] => match column.split_once('-') {
+            Some((column, end_column)) => Some((
+                line.parse().ok()?,
+                Some(column.parse().ok()?),
+                None,
+                Some(end_column.parse().ok()?),
+            )),
+            None => Some((line.parse().ok()?, Some(column.parse().ok()?), None, None)),
+        },
+        [line, column, end_line, end_column] => Some((
+            line.parse().ok()?,
+            Some(column.parse().ok()?),
+            Some(end_line.parse().ok()?),
+            Some(end_column.parse().ok()?),
+        )),
+        _ => None,
+    }
+}
+
+/// The subcategory is free text but should only consist of words.
+fn is_valid_subcategory(subcategory: &str) -> bool {
+    subcategory
+        .chars()
+        .all(|c| c.is_ascii_alphabetic() || c == ' ')
+}
+
+/// Checks if the origin looks like a file rather than
+/// the name of a tool e.g. `LINK` or `cl`.
+fn is_file_like(origin: &str) -> bool {
+    origin.contains(['\\', '/', '.'])
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_parse_line() {
+        let line = "C:\\src\\main.cpp(12,5): error C2065: 'x': undeclared identifier [C:\\src\\app.vcxproj]";
+        let actual = Diagnostic::parse_line(line)
+            .expect("It should be possible to parse a canonical error line.");
+        assert_eq!(actual.severity(), Severity::Error);
+        assert_eq!(actual.code(), Some("C2065"));
+        assert_eq!(actual.file(), Some(Path::new("C:\\src\\main.cpp")));
+        assert_eq!(actual.line(), Some(12));
+        assert_eq!(actual.column(), Some(5));
+        assert_eq!(actual.message(), "'x': undeclared identifier");
+        assert_eq!(actual.project(), Some(Path::new("C:\\src\\app.vcxproj")));
+    }
+
+    #[test]
+    fn test_diagnostic_parse_line_not_a_diagnostic() {
+        [
+            "",
+            "Build FAILED.",
+            "    4 Error(s)",
+            "  main.cpp",
+            "Project \"C:\\src\\error handling\\app.sln\" on node 1 (default targets).",
+            "C:\\src\\my error dir\\a.cpp is up to date.",
+        ]
+        .iter()
+        .for_each(|line| {
+            assert!(
+                Diagnostic::parse_line(line).is_none(),
+                "The line `{}` should not be parsed as a diagnostic.",
+                line
+            )
+        });
+    }
+
+    #[test]
+    fn test_strip_node_prefix() {
+        assert_eq!(strip_node_prefix("1>main.cpp"), "main.cpp");
+        assert_eq!(strip_node_prefix("12:3>main.cpp"), "main.cpp");
+        assert_eq!(strip_node_prefix("main.cpp"), "main.cpp");
+        assert_eq!(strip_node_prefix("a>b"), "a>b");
+    }
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(parse_location("1"), Some((1, None, None, None)));
+        assert_eq!(parse_location("1-4"), Some((1, None, Some(4), None)));
+        assert_eq!(parse_location("1,2"), Some((1, Some(2), None, None)));
+        assert_eq!(parse_location("1,2-8"), Some((1, Some(2), None, Some(8))));
+        assert_eq!(
+            parse_location("1,2,3,4"),
+            Some((1, Some(2), Some(3), Some(4)))
+        );
+        assert_eq!(parse_location("x86"), None);
+        assert_eq!(parse_location("1,2,3"), None);
+    }
+
+    #[test]
+    fn test_parse_removes_duplicates() {
+        let output = "\
+main.cpp(1,1): warning C4100: unused parameter [app.vcxproj]
+Build succeeded.
+  main.cpp(1,1): warning C4100: unused parameter [app.vcxproj]
+";
+        let actual = parse(output);
+        assert_eq!(actual.len(), 1, "Duplicated diagnostics should be removed.");
+        assert_eq!(actual[0].severity(), Severity::Warning);
+    }
+}
@@ -0,0 +1,309 @@
+//! Module containing the error type used by the crate.
+use crate::{build_outcome::BuildOutcome, selection::SelectionReport};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Result type used by the crate.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The errors that can occur when locating or running
+/// the Visual Studio tools.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The `vswhere.exe` binary could not be found in
+    /// any of the probed paths.
+    VsWhereNotFound { probed: Vec<PathBuf> },
+    /// `vswhere.exe` exited with a non-zero status, e.g. because
+    /// of an invalid argument. The stderr contains the reason.
+    VsWhereFailed {
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+    /// The output of `vswhere.exe` could not be interpreted.
+    VsWhereOutputInvalid {
+        reason: String,
+        source: Option<serde_json::Error>,
+    },
+    /// The setup instances directory, which contains the state of
+    /// the VS installations, does not exist.
+    SetupInstancesNotFound { dir: PathBuf },
+    /// JSON data that is not produced by `vswhere.exe`, e.g. the
+    /// state of a setup instance, could not be read or written.
+    Json {
+        reason: String,
+        source: serde_json::Error,
+    },
+    /// None of the VS installations matched the requirements.
+    /// The report describes why each installation was rejected.
+    NoMatchingInstallation(Box<SelectionReport>),
+    /// No Windows SDK could be found.
+    WinSdkNotFound {
+        reason: String,
+        source: Option<std::io::Error>,
+    },
+    /// No MSVC toolset could be found in the VS installation.
+    MsvcToolsetNotFound { reason: String },
+    /// A directory that is expected to be part of an
+    /// installation is missing.
+    MissingComponentDirectory { parent: PathBuf, dir: String },
+    /// A file that is expected to be part of an
+    /// installation is missing.
+    MissingComponentFile { parent: PathBuf, file: String },
+    /// A script, e.g. `vcvarsall.bat`, failed. The reason contains
+    /// the output of the script.
+    ScriptFailed { script: PathBuf, reason: String },
+    /// The msbuild executable could not be found.
+    MsBuildNotFound { path: PathBuf },
+    /// msbuild was executed but the build failed.
+    BuildFailed(Box<BuildOutcome>),
+    /// A string could not be parsed as a version.
+    InvalidVersion { value: String, reason: String },
+    /// A string did not match any known product line version.
+    UnknownProductLineVersion(String),
+    /// A string did not match any known architecture.
+    UnknownArch(String),
+    /// A child process did not finish in time and was killed.
+    TimedOut { timeout: Duration },
+    /// A child process was cancelled and was killed.
+    Cancelled,
+    /// An I/O error occurred.
+    Io(std::io::Error),
+}
+
+impl Error {
+    /// The outcome of the build if the error was caused by
+    /// a failed msbuild execution.
+    pub fn build_outcome(&self) -> Option<&BuildOutcome> {
+        match self {
+            Self::BuildFailed(outcome) => Some(outcome.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The report describing why each installation was rejected
+    /// if the error was caused by no installation matching the requirements.
+    pub fn selection_report(&self) -> Option<&SelectionReport> {
+        match self {
+            Self::NoMatchingInstallation(report) => Some(report.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// The [`ErrorKind`] used when converting the error
+    /// into a [`std::io::Error`].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::VsWhereNotFound { .. }
+            | Self::SetupInstancesNotFound { .. }
+            | Self::NoMatchingInstallation(_)
+            | Self::WinSdkNotFound { .. }
+            | Self::MsvcToolsetNotFound { .. }
+            | Self::MissingComponentDirectory { .. }
+            | Self::MissingComponentFile { .. }
+            | Self::MsBuildNotFound { .. } => ErrorKind::NotFound,
+            Self::VsWhereOutputInvalid { .. }
+            | Self::Json { .. }
+            | Self::InvalidVersion { .. }
+            | Self::UnknownProductLineVersion(_)
+            | Self::UnknownArch(_) => ErrorKind::InvalidData,
+            Self::VsWhereFailed { .. } | Self::BuildFailed(_) | Self::ScriptFailed { .. } => {
+                ErrorKind::Other
+            }
+            Self::TimedOut { .. } => ErrorKind::TimedOut,
+            Self::Cancelled => ErrorKind::Interrupted,
+            Self::Io(e) => e.kind(),
+        }
+    }
+
+    /// Creates a [`Error::VsWhereOutputInvalid`] without a source.
+    pub(crate) fn vswhere_output_invalid(reason: impl Into<String>) -> Self {
+        Self::VsWhereOutputInvalid {
+            reason: reason.into(),
+            source: None,
+        }
+    }
+
+    /// Creates a [`Error::WinSdkNotFound`] without a source.
+    pub(crate) fn win_sdk_not_found(reason: impl Into<String>) -> Self {
+        Self::WinSdkNotFound {
+            reason: reason.into(),
+            source: None,
+        }
+    }
+
+    /// Creates a [`Error::MsvcToolsetNotFound`].
+    pub(crate) fn msvc_toolset_not_found(reason: impl Into<String>) -> Self {
+        Self::MsvcToolsetNotFound {
+            reason: reason.into(),
+        }
+    }
+
+    /// Creates a [`Error::MissingComponentDirectory`].
+    pub(crate) fn missing_component_directory(parent: &Path, dir: &str) -> Self {
+        Self::MissingComponentDirectory {
+            parent: parent.to_path_buf(),
+            dir: dir.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::VsWhereNotFound { probed } => write!(
+                f,
+                "vswhere.exe could not be found, probed: [{}]",
+                probed
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Self::VsWhereFailed { status, stderr } if stderr.is_empty() => {
+                write!(f, "vswhere.exe failed with {}.", status)
+            }
+            Self::VsWhereFailed { status, stderr } => {
+                write!(f, "vswhere.exe failed with {}: {}", status, stderr)
+            }
+            Self::VsWhereOutputInvalid { reason, .. } => {
+                write!(f, "Invalid vswhere output: {}", reason)
+            }
+            Self::SetupInstancesNotFound { dir } => {
+                write!(f, "No setup instances found in {}", dir.display())
+            }
+            Self::Json { reason, source } => write!(f, "{} ({})", reason, source),
+            Self::NoMatchingInstallation(report) => write!(
+                f,
+                "No instance found that matched requirements (version range {}, {} candidate(s)).",
+                report.requested_range(),
+                report.candidates().len()
+            ),
+            Self::WinSdkNotFound { reason, .. } => {
+                write!(f, "No Windows SDK found: {}", reason)
+            }
+            Self::MsvcToolsetNotFound { reason } => {
+                write!(f, "No MSVC toolset found: {}", reason)
+            }
+            Self::MissingComponentDirectory { parent, dir } => write!(
+                f,
+                "{} does not contain the {} directory.",
+                parent.display(),
+                dir
+            ),
+            Self::MissingComponentFile { parent, file } => write!(
+                f,
+                "{} does not contain the {} file.",
+                parent.display(),
+                file
+            ),
+            Self::ScriptFailed { script, reason } => {
+                write!(f, "Failed to run {}: {}", script.display(), reason)
+            }
+            Self::MsBuildNotFound { path } => {
+                write!(f, "No msbuild executable found at {}", path.display())
+            }
+            Self::BuildFailed(outcome) => match outcome.exit_code() {
+                Some(code) => write!(f, "Failed to run msbuild: Exit code [{code}]"),
+                None => write!(f, "Failed to run msbuild"),
+            },
+            Self::InvalidVersion { value, reason } => {
+                write!(f, "Failed to parse `{}` as a version: {}", value, reason)
+            }
+            Self::UnknownProductLineVersion(value) => write!(
+                f,
+                "Product line version {} did not match any known values.",
+                value
+            ),
+            Self::UnknownArch(value) => {
+                write!(f, "Architecture {} did not match any known values.", value)
+            }
+            Self::TimedOut { timeout } => {
+                write!(f, "The process did not finish within {:?}.", timeout)
+            }
+            Self::Cancelled => write!(f, "The process was cancelled."),
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::VsWhereOutputInvalid {
+                source: Some(e), ..
+            } => Some(e),
+            Self::WinSdkNotFound {
+                source: Some(e), ..
+            } => Some(e),
+            Self::Json { source, .. } => Some(source),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            e => std::io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_error_into_io_error() {
+        let error = Error::missing_component_directory(Path::new("C:\\VS"), "VC");
+        let io_error: std::io::Error = error.into();
+        assert_eq!(io_error.kind(), ErrorKind::NotFound);
+        let inner = io_error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<Error>())
+            .expect("The io::Error should wrap the crate error.");
+        assert!(matches!(inner, Error::MissingComponentDirectory { .. }));
+
+        let io_error: std::io::Error =
+            Error::Io(std::io::Error::new(ErrorKind::PermissionDenied, "denied")).into();
+        assert_eq!(io_error.kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_error_source() {
+        let json_error = serde_json::from_str::<serde_json::Value>("{")
+            .expect_err("Invalid json should not be parsable.");
+        let error = Error::VsWhereOutputInvalid {
+            reason: "Failed to parse command output as json".to_string(),
+            source: Some(json_error),
+        };
+        assert!(error.source().is_some());
+        assert!(Error::Cancelled.source().is_none());
+
+        let json_error = serde_json::from_str::<serde_json::Value>("{")
+            .expect_err("Invalid json should not be parsable.");
+        let error = Error::Json {
+            reason: "Failed to parse state.json.".to_string(),
+            source: json_error,
+        };
+        assert!(error.source().is_some());
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!error.to_string().contains("vswhere"));
+    }
+}
@@ -0,0 +1,159 @@
+//! Internal module for finding files using the same kind of
+//! patterns as the `-find` argument of vswhere.
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Finds the files and directories below `root` that matches the
+/// pattern. The pattern segments are separated by `\` or `/` and
+/// support `*` (any characters), `?` (one character) and `**` (any
+/// number of directories). Names are compared ignoring ASCII case.
+///
+/// The matches are returned sorted without duplicates.
+pub(crate) fn find(root: &Path, pattern: &str) -> io::Result<Vec<PathBuf>> {
+    // Fail if the root can not be read at all.
+    root.read_dir()?;
+    let segments = pattern
+        .split(['\\', '/'])
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect::<Vec<&str>>();
+    let mut found = Vec::new();
+    if !segments.is_empty() {
+        walk(root, segments.as_slice(), &mut found);
+    }
+    found.sort();
+    found.dedup();
+    Ok(found)
+}
+
+// Internal function that matches the segments against the
+// entries of the directory.
+fn walk(dir: &Path, segments: &[&str], found: &mut Vec<PathBuf>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        return;
+    };
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries.filter_map(|e| e.ok()).collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    if *segment == "**" {
+        // Zero directories.
+        walk(dir, rest, found);
+        // One or more directories.
+        entries
+            .iter()
+            .map(|e| e.path())
+            .filter(|path| path.is_dir())
+            .for_each(|path| walk(path.as_path(), segments, found));
+        return;
+    }
+    entries
+        .iter()
+        .filter(|e| {
+            e.file_name()
+                .to_str()
+                .is_some_and(|name| matches(segment, name))
+        })
+        .map(|e| e.path())
+        .for_each(|path| {
+            if rest.is_empty() {
+                found.push(path);
+            } else if path.is_dir() {
+                walk(path.as_path(), rest, found);
+            }
+        });
+}
+
+// Internal function that checks if the name matches the
+// pattern segment containing `*` and `?` wildcards.
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let name = name.chars().collect::<Vec<char>>();
+    let (mut p, mut n) = (0, 0);
+    // The position of the last `*` and the name position it matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p].eq_ignore_ascii_case(&name[n])) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the last `*` match one more character.
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("cl.exe", "cl.exe"));
+        assert!(matches("CL.EXE", "cl.exe"));
+        assert!(matches("*.exe", "link.exe"));
+        assert!(matches("c?.exe", "cl.exe"));
+        assert!(matches("*", ""));
+        assert!(matches("14.*.*", "14.42.34433"));
+        assert!(matches("*a*b", "xaxxb"));
+        assert!(!matches("*.exe", "link.dll"));
+        assert!(!matches("c?.exe", "c.exe"));
+        assert!(!matches("cl.exe", "cl.exe.config"));
+    }
+
+    #[test]
+    fn test_find() {
+        let root = tempdir().expect("It should be possible to create a temporary directory.");
+        [
+            "VC/Tools/MSVC/14.42.34433/bin/Hostx64/x64/cl.exe",
+            "VC/Tools/MSVC/14.42.34433/bin/Hostx64/x86/cl.exe",
+            "VC/Tools/MSVC/14.38.33130/bin/Hostx64/x64/cl.exe",
+            "VC/Tools/MSVC/14.42.34433/bin/Hostx64/x64/link.exe",
+            "Common7/IDE/devenv.com",
+        ]
+        .iter()
+        .for_each(|file| {
+            let path = root.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap())
+                .expect("It should be possible to create the directories.");
+            std::fs::write(path, "").expect("It should be possible to create the file.");
+        });
+
+        let actual = find(root.path(), "VC\\Tools\\MSVC\\**\\Hostx64\\x64\\cl.exe")
+            .expect("It should be possible to find files.");
+        assert_eq!(
+            actual,
+            [
+                root.path()
+                    .join("VC/Tools/MSVC/14.38.33130/bin/Hostx64/x64/cl.exe"),
+                root.path()
+                    .join("VC/Tools/MSVC/14.42.34433/bin/Hostx64/x64/cl.exe"),
+            ]
+        );
+
+        let actual = find(root.path(), "**/*.com").expect("It should be possible to find files.");
+        assert_eq!(actual, [root.path().join("Common7/IDE/devenv.com")]);
+
+        let actual = find(root.path(), "**\\cl.exe").expect("It should be possible to find files.");
+        assert_eq!(actual.len(), 3);
+
+        let actual = find(root.path(), "VC/Tools/MSVC/*")
+            .expect("It should be possible to find directories.");
+        assert_eq!(actual.len(), 2);
+
+        assert!(find(root.path().join("missing").as_path(), "**\\cl.exe").is_err());
+    }
+}
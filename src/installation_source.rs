@@ -0,0 +1,343 @@
+//! Module containing the sources that the VS installations
+//! can be discovered from.
+use crate::{
+    error::{Error, Result},
+    vs_installation::VsInstallation,
+    vs_where::VsWhere,
+};
+use serde_json::Value;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// Trait for a source of VS installation instances.
+///
+/// The instances are returned in the instance format used by vswhere,
+/// i.e. the objects of the array returned by `vswhere -format json`,
+/// so the selection treats all the sources in the same way. Instances
+/// that can not be interpreted as installations are reported as
+/// rejected candidates when selecting an installation.
+///
+/// The crate provides the following sources:
+/// - [`crate::VsWhere`] runs the `vswhere.exe` binary.
+/// - [`crate::VsSetupInstances`] reads the state of the setup instances.
+/// - [`JsonFileSource`] reads the instances from a file.
+/// - [`InstallationList`] contains the installations in memory.
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{InstallationCriteria, JsonFileSource, VsInstallation};
+///
+/// let source = JsonFileSource::new("vswhere.json");
+/// let installation = VsInstallation::find_in(&source, &InstallationCriteria::new());
+/// ```
+pub trait InstallationSource {
+    /// The instances in the instance format used by vswhere.
+    fn instances(&self) -> Result<Vec<Value>>;
+
+    /// The instances including their packages, i.e. workloads and
+    /// components, which are needed when the selection requires packages.
+    /// Sources that only report the packages on request, e.g. vswhere,
+    /// override this. By default the instances are returned.
+    fn instances_with_packages(&self) -> Result<Vec<Value>> {
+        self.instances()
+    }
+}
+
+/// Crate function that returns the source used when no source is
+/// specified, i.e. the file in the `VS_WHERE_JSON` environment
+/// variable if it is set and otherwise vswhere.
+pub(crate) fn default_source() -> Result<Box<dyn InstallationSource>> {
+    match JsonFileSource::from_env() {
+        Some(source) => Ok(Box::new(source)),
+        None => VsWhere::find_vswhere().map(|vswhere| Box::new(vswhere) as _),
+    }
+}
+
+/// Source that reads the instances from a file containing
+/// the output of `vswhere -format json`.
+///
+/// This makes it possible to capture the instances on one machine
+/// and replay them on another, e.g. on a CI machine without VS.
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{InstallationCriteria, JsonFileSource, VsInstallation, VsWhere};
+///
+/// // Capture the instances on a machine with VS.
+/// let vswhere = VsWhere::find_vswhere().expect("vswhere should exist");
+/// JsonFileSource::capture(&vswhere, "vswhere.json").expect("The capture should succeed");
+///
+/// // Replay the instances elsewhere.
+/// let source = JsonFileSource::new("vswhere.json");
+/// let report = VsInstallation::explain_in(&source, &InstallationCriteria::new());
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonFileSource {
+    path: PathBuf,
+}
+
+impl JsonFileSource {
+    pub(crate) const ENV_KEY: &'static str = "VS_WHERE_JSON";
+
+    /// Creates a JsonFileSource object for the file.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Creates a JsonFileSource object for the file in the
+    /// `VS_WHERE_JSON` environment variable if it is set.
+    pub fn from_env() -> Option<Self> {
+        Self::from_env_with(|key| std::env::var_os(key))
+    }
+
+    /// Writes the instances of the source, including their packages, to
+    /// the file in the same format as `vswhere -format json -include packages`
+    /// and creates a JsonFileSource object that replays them. Including the
+    /// packages makes the replay select the same installation as the source
+    /// when the criteria requires packages.
+    pub fn capture(source: &dyn InstallationSource, path: impl Into<PathBuf>) -> Result<Self> {
+        let capture = Self::new(path);
+        source
+            .instances_with_packages()
+            .and_then(|instances| {
+                serde_json::to_string_pretty(&instances).map_err(|e| Error::Json {
+                    reason: "Failed to serialize the instances.".to_string(),
+                    source: e,
+                })
+            })
+            .and_then(|json| {
+                debug!("Capturing instances to {}", capture.path.display());
+                std::fs::write(&capture.path, json).map_err(Error::from)
+            })
+            .map(|_| capture)
+    }
+
+    // Internal function that creates the source from the environment
+    // variable using the function for reading environment variables.
+    fn from_env_with(env_fn: impl Fn(&str) -> Option<OsString>) -> Option<Self> {
+        let value = env_fn(Self::ENV_KEY).filter(|value| !value.is_empty());
+        trace!("Consulted {}: {:?}", Self::ENV_KEY, value);
+        value.map(Self::new)
+    }
+
+    /// The path of the file.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+impl InstallationSource for JsonFileSource {
+    fn instances(&self) -> Result<Vec<Value>> {
+        trace!("Reading instances from {}", self.path.display());
+        std::fs::read_to_string(&self.path)
+            .map_err(Error::from)
+            .and_then(|content| VsInstallation::parse_from_json(&content))
+    }
+}
+
+/// Source that contains the installations in memory, e.g.
+/// installations that have been listed from another source.
+#[derive(Debug, Clone, Default)]
+pub struct InstallationList {
+    installations: Vec<VsInstallation>,
+}
+
+impl InstallationList {
+    /// Creates an InstallationList object containing the installations.
+    pub fn new(installations: Vec<VsInstallation>) -> Self {
+        Self { installations }
+    }
+
+    /// The installations in the list.
+    pub fn installations(&self) -> &[VsInstallation] {
+        self.installations.as_slice()
+    }
+}
+
+impl From<Vec<VsInstallation>> for InstallationList {
+    fn from(installations: Vec<VsInstallation>) -> Self {
+        Self::new(installations)
+    }
+}
+
+impl InstallationSource for InstallationList {
+    fn instances(&self) -> Result<Vec<Value>> {
+        self.installations
+            .iter()
+            .map(|installation| {
+                serde_json::to_value(installation).map_err(|e| Error::Json {
+                    reason: "Failed to serialize installation instance.".to_string(),
+                    source: e,
+                })
+            })
+            .collect()
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::selection::InstallationCriteria;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_json_file_source() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let path = dir.path().join("vswhere.json");
+        std::fs::write(
+            &path,
+            r#"[{"installationPath": "C:\\VS\\2022\\BuildTools", "installationVersion": "17.12.35506.116"}, {"instanceId": "broken"}]"#,
+        )
+        .expect("It should be possible to write the file.");
+
+        let instances = JsonFileSource::new(path.as_path())
+            .instances()
+            .expect("It should be possible to read the instances.");
+        assert_eq!(instances.len(), 2);
+
+        assert!(matches!(
+            JsonFileSource::new(dir.path().join("missing.json")).instances(),
+            Err(Error::Io(_))
+        ));
+
+        std::fs::write(&path, "{}").expect("It should be possible to write the file.");
+        assert!(matches!(
+            JsonFileSource::new(path.as_path()).instances(),
+            Err(Error::VsWhereOutputInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_installation_list_round_trip() {
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": "C:\\VS\\2022\\BuildTools",
+            "installationVersion": "17.12.35506.116",
+            "instanceId": "3f2b1c9a",
+            "productId": "Microsoft.VisualStudio.Product.BuildTools",
+            "catalog": { "productLineVersion": "2022" },
+            "packages": [{ "id": "Microsoft.VisualCpp.Tools.Core", "type": "Vsix" }],
+        }))
+        .expect("It should be possible to parse the installation.");
+
+        let instances = InstallationList::from(vec![installation])
+            .instances()
+            .expect("It should be possible to serialize the installations.");
+        let actual = VsInstallation::from_json(instances[0].clone())
+            .expect("It should be possible to parse the serialized installation.");
+        assert_eq!(actual.path(), Path::new("C:\\VS\\2022\\BuildTools"));
+        assert_eq!(actual.instance_id(), Some("3f2b1c9a"));
+        assert_eq!(
+            actual.product_id(),
+            Some("Microsoft.VisualStudio.Product.BuildTools")
+        );
+        assert_eq!(
+            actual.catalog().and_then(|c| c.product_line_version()),
+            Some("2022")
+        );
+        assert!(actual.has_package("Microsoft.VisualCpp.Tools.Core"));
+        assert_eq!(actual.packages()[0].package_type(), Some("Vsix"));
+    }
+
+    #[test]
+    fn test_from_env_with() {
+        let source = JsonFileSource::from_env_with(|key| match key {
+            "VS_WHERE_JSON" => Some(OsString::from("/captures/vswhere.json")),
+            _ => None,
+        })
+        .expect("It should be possible to create the source from the environment variable.");
+        assert_eq!(source.path(), Path::new("/captures/vswhere.json"));
+
+        assert!(JsonFileSource::from_env_with(|_| None).is_none());
+        assert!(JsonFileSource::from_env_with(|_| Some(OsString::new())).is_none());
+    }
+
+    #[test]
+    fn test_capture() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": "C:\\VS\\2022\\BuildTools",
+            "installationVersion": "17.12.35506.116",
+        }))
+        .expect("It should be possible to parse the installation.");
+
+        let source = JsonFileSource::capture(
+            &InstallationList::new(vec![installation]),
+            dir.path().join("vswhere.json"),
+        )
+        .expect("It should be possible to capture the instances.");
+        let instances = source
+            .instances()
+            .expect("It should be possible to replay the instances.");
+        assert_eq!(instances.len(), 1);
+        assert_eq!(
+            instances[0].get("installationVersion"),
+            Some(&Value::from("17.12.35506.116"))
+        );
+
+        assert!(JsonFileSource::capture(&source, dir.path().join("missing/vswhere.json")).is_err());
+    }
+
+    // Source that, like vswhere, only reports the packages of the
+    // instances when they are requested.
+    struct PackagesOnRequest;
+
+    impl InstallationSource for PackagesOnRequest {
+        fn instances(&self) -> Result<Vec<Value>> {
+            self.instances_with_packages().map(|instances| {
+                instances
+                    .into_iter()
+                    .map(|mut instance| {
+                        instance
+                            .as_object_mut()
+                            .map(|instance| instance.remove("packages"));
+                        instance
+                    })
+                    .collect()
+            })
+        }
+
+        fn instances_with_packages(&self) -> Result<Vec<Value>> {
+            Ok(vec![
+                serde_json::json!({
+                    "installationPath": "C:\\VS\\2022\\Community",
+                    "installationVersion": "17.12.35506.116",
+                    "instanceId": "a1b2c3d4",
+                    "productId": "Microsoft.VisualStudio.Product.Community",
+                    "catalog": { "productLineVersion": "2022" },
+                    "packages": [{ "id": "Microsoft.VisualStudio.Workload.ManagedDesktop", "type": "Workload" }],
+                }),
+                serde_json::json!({
+                    "installationPath": "C:\\VS\\2022\\BuildTools",
+                    "installationVersion": "17.10.35013.160",
+                    "instanceId": "3f2b1c9a",
+                    "productId": "Microsoft.VisualStudio.Product.BuildTools",
+                    "catalog": { "productLineVersion": "2022" },
+                    "packages": [{ "id": "Microsoft.VisualStudio.Component.VC.Tools.x86.x64", "type": "Component" }],
+                }),
+            ])
+        }
+    }
+
+    #[test]
+    fn test_capture_replays_required_packages() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let criteria = InstallationCriteria::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64");
+
+        let live = VsInstallation::find_in(&PackagesOnRequest, &criteria)
+            .expect("It should be possible to find the installation in the source.");
+        let capture = JsonFileSource::capture(&PackagesOnRequest, dir.path().join("vswhere.json"))
+            .expect("It should be possible to capture the instances.");
+        let replayed = VsInstallation::find_in(&capture, &criteria)
+            .expect("It should be possible to find the installation in the capture.");
+        assert_eq!(live.instance_id(), Some("3f2b1c9a"));
+        assert_eq!(replayed.instance_id(), live.instance_id());
+    }
+}
@@ -11,34 +11,98 @@
 //!   overwrite the default path where the crate tries to locate
 //!   the `vswhere.exe` binary.
 //!
+//! - The `VS_WHERE_JSON` environment variable can be used in order to
+//!   replay the output of `vswhere -format json` from a file instead of
+//!   running vswhere, e.g. in order to debug the selection on a machine
+//!   without Visual Studio. See [`JsonFileSource`].
+//!
 //! - The `VS_INSTALLATION_PATH` environment variable can be used in order
 //!   to overwrite specify a path to Visual Studio installation
 //!   Note! The path must still lead to an installation that fulfills the version
 //!   requirements otherwise the crate will try to probe the system
-//!   for a suitable version.
+//!   for a suitable version. If the path is within several installations
+//!   the installation with the deepest path is selected.
+//!
+//! - The `VS_INSTANCE_ID` environment variable can be used in order to
+//!   pin the selection to the Visual Studio installation with the
+//!   instance id. It overwrites any instance id specified in the
+//!   [`InstallationCriteria`].
 //!
 //! - The `WIN_SDK_PATH` environment variable can be used in order to
 //!   to overwrite in what location the library will search for
 //!   WinSDK installations.
+//!
+//! The variables are listed in [`ENV_VARS`]. Cargo does not rerun a build
+//! script when one of them changes unless the build script asks for it, so
+//! a build script that uses the crate should call
+//! [`CargoReport::emit_env_directives`], which prints a
+//! `cargo:rerun-if-env-changed` line for each of them.
+//!
+//! # Features
+//! - `log`: Routes the diagnostic output of the crate, e.g. the probed
+//!   paths, the consulted environment variables and the vswhere arguments,
+//!   through the [`log`](https://docs.rs/log) facade. Without the feature
+//!   no diagnostic output is produced.
 use std::{
     convert::TryFrom,
-    io::{Error, ErrorKind},
     path::{Path, PathBuf},
 };
 
+#[macro_use]
+mod logging;
+mod glob;
+mod process;
 mod versions;
 
 pub(crate) mod vs_paths;
 
+pub mod arch;
+pub mod build_outcome;
+pub mod cargo_report;
+pub mod diagnostics;
+pub mod error;
+pub mod installation_source;
+pub mod msbuild_command;
+pub mod selection;
+pub mod vs_dev_cmd;
+pub mod vs_dev_env;
 pub mod vs_installation;
 pub mod vs_llvm;
+pub mod vs_msvc_toolset;
+pub mod vs_setup_instances;
 pub mod vs_where;
+pub mod vs_where_query;
 pub mod win_sdk;
 
-pub use versions::{VsInstallationVersion, VsProductLineVersion};
+pub use arch::{Arch, HostTarget};
+pub use build_outcome::BuildOutcome;
+pub use cargo_report::CargoReport;
+pub use diagnostics::{Diagnostic, Severity};
+pub use error::{Error, Result};
+pub use installation_source::{InstallationList, InstallationSource, JsonFileSource};
+pub use msbuild_command::{MsBuildCommand, Verbosity};
+pub use process::{CancellationToken, OutputStream};
+pub use selection::{
+    Candidate, CandidateStatus, InstallationCriteria, RejectionReason, SelectionReport, VsProduct,
+};
+pub use versions::{MsvcToolsetVersion, VsInstallationVersion, VsProductLineVersion};
+pub use vs_dev_cmd::VsDevCmd;
+pub use vs_dev_env::VsDevEnv;
 pub use vs_installation::VsInstallation;
 pub use vs_llvm::VsLlvm;
+pub use vs_msvc_toolset::VsMsvcToolset;
+pub use vs_setup_instances::VsSetupInstances;
 pub use vs_where::VsWhere;
+pub use vs_where_query::VsWhereQuery;
+
+/// The environment variables that are read by the crate.
+pub const ENV_VARS: [&str; 5] = [
+    VsWhere::ENV_KEY,
+    JsonFileSource::ENV_KEY,
+    VsInstallation::ENV_KEY,
+    VsInstallation::INSTANCE_ID_ENV_KEY,
+    win_sdk::WinSdk::ENV_KEY,
+];
 
 /// Type for finding and interactive with
 /// the msbuild executable.
@@ -53,14 +117,14 @@ impl MsBuild {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// use msbuild::MsBuild;
     ///
     /// let product_line_version: Option<&str> = Some("2017");
     /// let msbuild: MsBuild = MsBuild::find_msbuild(product_line_version)
     ///     .expect("A 2017 VS installation should exist");
     /// ```
-    pub fn find_msbuild(product_line_version: Option<&str>) -> std::io::Result<Self> {
+    pub fn find_msbuild(product_line_version: Option<&str>) -> Result<Self> {
         product_line_version
             .map(VsProductLineVersion::try_from)
             .transpose()
@@ -92,56 +156,99 @@ impl MsBuild {
     pub fn find_msbuild_in_range(
         max: Option<VsInstallationVersion>,
         min: Option<VsInstallationVersion>,
-    ) -> std::io::Result<Self> {
+    ) -> Result<Self> {
         VsInstallation::find_in_range(max, min)
             .and_then(|vs_installation| Self::try_from(&vs_installation))
     }
 
+    /// Finds the msbuild executable of the VS installation that best
+    /// fulfills the criteria.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{InstallationCriteria, MsBuild, VsProduct};
+    ///
+    /// // Prefer the build tools and never use the community edition.
+    /// let criteria = InstallationCriteria::new()
+    ///     .exclude_product(VsProduct::Community)
+    ///     .prefer_products([VsProduct::BuildTools]);
+    /// let msbuild = MsBuild::find_msbuild_with(&criteria);
+    /// ```
+    pub fn find_msbuild_with(criteria: &InstallationCriteria) -> Result<Self> {
+        VsInstallation::find(criteria).and_then(|vs_installation| Self::try_from(&vs_installation))
+    }
+
+    /// Finds the msbuild executable of the VS installation of the source
+    /// that best fulfills the criteria.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{InstallationCriteria, JsonFileSource, MsBuild};
+    ///
+    /// // Use the instances recorded from vswhere.
+    /// let source = JsonFileSource::new("vswhere.json");
+    /// let msbuild = MsBuild::find_msbuild_in(&source, &InstallationCriteria::new());
+    /// ```
+    pub fn find_msbuild_in(
+        source: &dyn InstallationSource,
+        criteria: &InstallationCriteria,
+    ) -> Result<Self> {
+        VsInstallation::find_in(source, criteria)
+            .and_then(|vs_installation| Self::try_from(&vs_installation))
+    }
+
+    /// The path of the msbuild executable.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Creates a [`MsBuildCommand`] for this msbuild executable
+    /// which can be used to assemble the arguments in a typed way.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{MsBuild, Verbosity};
+    ///
+    /// let msbuild = MsBuild::find_msbuild(None).expect("msbuild should exist");
+    /// msbuild
+    ///     .command()
+    ///     .project("project.sln")
+    ///     .configuration("Release")
+    ///     .verbosity(Verbosity::Minimal)
+    ///     .run()
+    ///     .expect("The build should succeed");
+    /// ```
+    pub fn command(&self) -> MsBuildCommand {
+        MsBuildCommand::new(self.path.as_path())
+    }
+
     /// Executes msbuild using the provided project_path and
     /// the provided arguments.
-    pub fn run(&self, project_path: &Path, args: &[&str]) -> std::io::Result<()> {
-        if !self.path.as_path().exists() {
-            return Err(Error::new(
-                ErrorKind::NotFound,
-                format!("Could not find [{}].", self.path.to_string_lossy()),
-            ));
-        }
-        std::process::Command::new(self.path.as_path())
+    ///
+    /// The output of msbuild is captured in the returned [`BuildOutcome`].
+    /// If msbuild fails the outcome can be extracted from the error
+    /// using [`Error::build_outcome`].
+    pub fn run(&self, project_path: &Path, args: &[&str]) -> Result<BuildOutcome> {
+        self.command()
             .current_dir(project_path)
-            .args(args)
-            .output()
-            .and_then(|out| {
-                if out.status.success() {
-                    Ok(())
-                } else {
-                    use std::io::Write;
-                    std::io::stdout().write_all(&out.stdout)?;
-                    let error_message = if let Some(code) = out.status.code() {
-                        &format!("Failed to run msbuild: Exit code [{code}]")
-                    } else {
-                        "Failed to run msbuild"
-                    };
-                    Err(Error::new(
-                        ErrorKind::Other,
-                        format!("Failed to run msbuild: {error_message}"),
-                    ))
-                }
-            })
+            .args(args.iter().copied())
+            .run()
     }
 }
 
 impl TryFrom<&VsInstallation> for MsBuild {
     type Error = Error;
 
-    fn try_from(vs_installation: &VsInstallation) -> std::io::Result<MsBuild> {
+    fn try_from(vs_installation: &VsInstallation) -> Result<MsBuild> {
         let path: PathBuf = vs_installation
             .path()
             .join("MsBuild/Current/Bin/msbuild.exe");
+        trace!("Probing for msbuild at {}", path.display());
         if !path.is_file() {
-            return Err(Error::new(
-                ErrorKind::NotFound,
-                format!("No msbuild executable found at {}", path.display()),
-            ));
+            return Err(Error::MsBuildNotFound { path });
         }
         Ok(MsBuild { path })
     }
@@ -0,0 +1,42 @@
+//! Internal module containing the macros used for diagnostic output.
+//!
+//! When the `log` feature is enabled the macros forward to the
+//! [`log`](https://docs.rs/log) facade, otherwise they compile to
+//! nothing. The crate never writes diagnostic output to stdout since
+//! cargo interprets the stdout of build scripts.
+
+/// Logs a message at the trace level.
+macro_rules! trace {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::trace!(target: "msbuild", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+/// Logs a message at the debug level.
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::debug!(target: "msbuild", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+/// Logs a message at the warn level.
+macro_rules! warn {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::warn!(target: "msbuild", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
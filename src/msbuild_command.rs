@@ -0,0 +1,564 @@
+//! Module containing a typed builder for msbuild invocations.
+use crate::{
+    arch::Arch,
+    build_outcome::BuildOutcome,
+    cargo_report::CargoReport,
+    diagnostics::{self, Diagnostic},
+    error::{Error, Result},
+    process::{self, CancellationToken, OutputStream, ProcessLimits},
+};
+use std::{
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    time::{Duration, Instant},
+};
+
+/// The amount of information msbuild writes to the build log.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Verbosity {
+    Quiet,
+    Minimal,
+    Normal,
+    Detailed,
+    Diagnostic,
+}
+
+impl Verbosity {
+    /// The value used by the `/verbosity` switch.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Quiet => "quiet",
+            Self::Minimal => "minimal",
+            Self::Normal => "normal",
+            Self::Detailed => "detailed",
+            Self::Diagnostic => "diagnostic",
+        }
+    }
+}
+
+/// Builder for a msbuild command line.
+///
+/// The command line is rendered by [`MsBuildCommand::to_args`] which
+/// makes it possible to inspect what would be executed without
+/// actually running msbuild.
+///
+/// # Examples
+///
+/// ```
+/// use msbuild::msbuild_command::{MsBuildCommand, Verbosity};
+///
+/// let command = MsBuildCommand::new("msbuild.exe")
+///     .project("project.sln")
+///     .target("Build")
+///     .configuration("Release")
+///     .platform("x64")
+///     .verbosity(Verbosity::Minimal)
+///     .max_cpu_count(None);
+///
+/// assert_eq!(
+///     command.to_args(),
+///     [
+///         "project.sln",
+///         "/t:Build",
+///         "/p:Configuration=Release",
+///         "/p:Platform=x64",
+///         "/v:minimal",
+///         "/m",
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct MsBuildCommand {
+    program: PathBuf,
+    current_dir: Option<PathBuf>,
+    project: Option<PathBuf>,
+    targets: Vec<String>,
+    properties: Vec<(String, String)>,
+    verbosity: Option<Verbosity>,
+    max_cpu_count: Option<Option<u32>>,
+    node_reuse: Option<bool>,
+    restore: bool,
+    response_files: Vec<PathBuf>,
+    extra_args: Vec<String>,
+    report_to_cargo: bool,
+    limits: ProcessLimits,
+}
+
+impl MsBuildCommand {
+    const CONFIGURATION_PROPERTY: &'static str = "Configuration";
+    const PLATFORM_PROPERTY: &'static str = "Platform";
+
+    /// Creates a command that will execute the msbuild binary
+    /// located at `program`.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            current_dir: None,
+            project: None,
+            targets: Vec::new(),
+            properties: Vec::new(),
+            verbosity: None,
+            max_cpu_count: None,
+            node_reuse: None,
+            restore: false,
+            response_files: Vec::new(),
+            extra_args: Vec::new(),
+            report_to_cargo: false,
+            limits: ProcessLimits::default(),
+        }
+    }
+
+    /// The msbuild binary that will be executed.
+    pub fn program(&self) -> &Path {
+        self.program.as_path()
+    }
+
+    /// Sets the working directory of the msbuild process.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the project or solution file that should be built.
+    pub fn project(mut self, project: impl Into<PathBuf>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Adds a target that should be built (`/t`).
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.targets.push(target.into());
+        self
+    }
+
+    /// Adds multiple targets that should be built (`/t`).
+    pub fn targets<I, S>(mut self, targets: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.targets.extend(targets.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets a property (`/p`). Setting a property that already
+    /// has been set replaces the previous value.
+    pub fn property(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        let value = value.into();
+        match self
+            .properties
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(&name))
+        {
+            Some((_, v)) => *v = value,
+            None => self.properties.push((name, value)),
+        }
+        self
+    }
+
+    /// Sets the `Configuration` property e.g. `Release`.
+    pub fn configuration(self, configuration: impl Into<String>) -> Self {
+        self.property(Self::CONFIGURATION_PROPERTY, configuration)
+    }
+
+    /// Sets the `Platform` property e.g. `x64`.
+    pub fn platform(self, platform: impl Into<String>) -> Self {
+        self.property(Self::PLATFORM_PROPERTY, platform)
+    }
+
+    /// Sets the `Platform` property to the platform of the target
+    /// architecture e.g. `Win32` for [`Arch::X86`].
+    pub fn target_arch(self, target: Arch) -> Self {
+        self.platform(target.msbuild_platform())
+    }
+
+    /// Sets the verbosity of the build log (`/v`).
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+
+    /// Enables parallel builds (`/m`). If no count is provided then
+    /// msbuild will use as many processes as there are processors.
+    pub fn max_cpu_count(mut self, count: Option<u32>) -> Self {
+        self.max_cpu_count = Some(count);
+        self
+    }
+
+    /// Enables or disables the reuse of msbuild nodes (`/nr`).
+    pub fn node_reuse(mut self, enabled: bool) -> Self {
+        self.node_reuse = Some(enabled);
+        self
+    }
+
+    /// Runs the `Restore` target before the other targets (`/restore`).
+    pub fn restore(mut self, enabled: bool) -> Self {
+        self.restore = enabled;
+        self
+    }
+
+    /// Adds a response file with additional arguments (`@file`).
+    pub fn response_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.response_files.push(path.into());
+        self
+    }
+
+    /// Adds a raw argument that is passed to msbuild as is.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.extra_args.push(arg.into());
+        self
+    }
+
+    /// Adds raw arguments that are passed to msbuild as is.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Enables forwarding of the diagnostics to cargo when msbuild is
+    /// executed from a build script. Each diagnostic is printed as a
+    /// `cargo:warning` and a `cargo:rerun-if-changed` is printed for
+    /// the project files involved in the build, see [`CargoReport`].
+    pub fn report_to_cargo(mut self, enabled: bool) -> Self {
+        self.report_to_cargo = enabled;
+        self
+    }
+
+    /// Sets the maximum time msbuild is allowed to run. When the time
+    /// is exceeded msbuild, and the nodes it started, is killed and
+    /// [`Error::TimedOut`] is returned.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a token that can be used to cancel the execution of msbuild
+    /// from another thread. When cancelled msbuild, and the nodes it
+    /// started, is killed and [`Error::Cancelled`] is returned.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.limits.cancellation_token = Some(token);
+        self
+    }
+
+    /// The arguments that msbuild will be executed with.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(project) = &self.project {
+            args.push(project.to_string_lossy().into_owned());
+        }
+        if !self.targets.is_empty() {
+            args.push(format!("/t:{}", self.targets.join(";")));
+        }
+        args.extend(
+            self.properties
+                .iter()
+                .map(|(name, value)| format!("/p:{}={}", name, escape_property_value(value))),
+        );
+        if let Some(verbosity) = self.verbosity {
+            args.push(format!("/v:{}", verbosity.as_str()));
+        }
+        match self.max_cpu_count {
+            Some(Some(count)) => args.push(format!("/m:{}", count)),
+            Some(None) => args.push("/m".to_string()),
+            None => {}
+        }
+        if let Some(node_reuse) = self.node_reuse {
+            args.push(format!("/nr:{}", node_reuse));
+        }
+        if self.restore {
+            args.push("/restore".to_string());
+        }
+        args.extend(
+            self.response_files
+                .iter()
+                .map(|p| format!("@{}", p.to_string_lossy())),
+        );
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+
+    /// The full command line, including the program, as a
+    /// single string. Arguments containing spaces are quoted.
+    ///
+    /// This is intended for logging and diagnostics.
+    pub fn command_line(&self) -> String {
+        std::iter::once(self.program.to_string_lossy().into_owned())
+            .chain(self.to_args())
+            .map(|arg| {
+                if arg.contains(' ') {
+                    format!("\"{}\"", arg)
+                } else {
+                    arg
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Executes msbuild with the rendered arguments and captures
+    /// its output.
+    ///
+    /// If msbuild exits with a failure then [`Error::BuildFailed`]
+    /// containing the [`BuildOutcome`] is returned.
+    pub fn run(&self) -> Result<BuildOutcome> {
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        self.execute(|stream, line| {
+            let buffer = match stream {
+                OutputStream::Stdout => &mut stdout,
+                OutputStream::Stderr => &mut stderr,
+            };
+            buffer.push_str(line);
+            buffer.push('\n');
+        })
+        .and_then(|(status, duration, diagnostics)| {
+            self.finish(BuildOutcome::new(
+                status,
+                stdout,
+                stderr,
+                duration,
+                diagnostics,
+            ))
+        })
+    }
+
+    /// Executes msbuild with the rendered arguments and calls `on_line`
+    /// for each line of output as soon as msbuild has written it.
+    ///
+    /// The output is not kept in memory so the stdout and stderr of the
+    /// returned [`BuildOutcome`] are empty, but the diagnostics are
+    /// collected while the build is running.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{MsBuild, OutputStream};
+    ///
+    /// let msbuild = MsBuild::find_msbuild(None).expect("msbuild should exist");
+    /// let outcome = msbuild
+    ///     .command()
+    ///     .project("project.sln")
+    ///     .run_streaming(|stream, line| match stream {
+    ///         OutputStream::Stdout => println!("{}", line),
+    ///         OutputStream::Stderr => eprintln!("{}", line),
+    ///     })
+    ///     .expect("The build should succeed");
+    /// ```
+    pub fn run_streaming(
+        &self,
+        mut on_line: impl FnMut(OutputStream, &str),
+    ) -> Result<BuildOutcome> {
+        self.execute(|stream, line| on_line(stream, line)).and_then(
+            |(status, duration, diagnostics)| {
+                self.finish(BuildOutcome::new(
+                    status,
+                    String::new(),
+                    String::new(),
+                    duration,
+                    diagnostics,
+                ))
+            },
+        )
+    }
+
+    // Internal function that spawns msbuild and collects the diagnostics
+    // while passing each line of output to `on_line`.
+    fn execute(
+        &self,
+        mut on_line: impl FnMut(OutputStream, &str),
+    ) -> Result<(ExitStatus, Duration, Vec<Diagnostic>)> {
+        if !self.program.as_path().exists() {
+            return Err(Error::MsBuildNotFound {
+                path: self.program.clone(),
+            });
+        }
+        let mut command = std::process::Command::new(self.program.as_path());
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        command.args(self.to_args());
+        debug!("Running {}", self.command_line());
+
+        let mut diagnostics = Vec::new();
+        let start = Instant::now();
+        process::run_with_lines(&mut command, &self.limits, |stream, line| {
+            if stream == OutputStream::Stdout {
+                if let Some(diagnostic) = Diagnostic::parse_line(line) {
+                    diagnostics::push_unique(&mut diagnostics, diagnostic);
+                }
+            }
+            on_line(stream, line);
+        })
+        .map(|status| (status, start.elapsed(), diagnostics))
+    }
+
+    // Internal function that reports the outcome to cargo, if enabled,
+    // and turns a failed build into an error.
+    fn finish(&self, outcome: BuildOutcome) -> Result<BuildOutcome> {
+        if self.report_to_cargo {
+            self.cargo_report(&outcome).emit();
+        }
+        if outcome.success() {
+            Ok(outcome)
+        } else {
+            Err(Error::BuildFailed(Box::new(outcome)))
+        }
+    }
+
+    // Internal function for creating the cargo report which includes
+    // the project that was built.
+    fn cargo_report(&self, outcome: &BuildOutcome) -> CargoReport {
+        let report = CargoReport::new(outcome);
+        match (&self.project, &self.current_dir) {
+            (Some(project), Some(dir)) => report.with_project(dir.join(project).as_path()),
+            (Some(project), None) => report.with_project(project.as_path()),
+            (None, _) => report,
+        }
+    }
+}
+
+/// Escapes the characters that msbuild would otherwise interpret
+/// as property separators. The `%` is escaped first since msbuild
+/// interprets `%XX` as an escape sequence.
+fn escape_property_value(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace(';', "%3B")
+        .replace(',', "%2C")
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_msbuild_command_empty() {
+        let command = MsBuildCommand::new("msbuild.exe");
+        assert!(
+            command.to_args().is_empty(),
+            "A command without any settings should not have any arguments."
+        );
+        assert_eq!(command.command_line(), "msbuild.exe");
+    }
+
+    #[test]
+    fn test_msbuild_command_to_args() {
+        let command = MsBuildCommand::new("msbuild.exe")
+            .project("project.vcxproj")
+            .targets(["Clean", "Build"])
+            .configuration("Debug")
+            .platform("Win32")
+            .property("OutDir", "out")
+            .configuration("Release")
+            .verbosity(Verbosity::Quiet)
+            .max_cpu_count(Some(4))
+            .node_reuse(false)
+            .restore(true)
+            .response_file("extra.rsp")
+            .arg("/nologo");
+
+        assert_eq!(
+            command.to_args(),
+            [
+                "project.vcxproj",
+                "/t:Clean;Build",
+                "/p:Configuration=Release",
+                "/p:Platform=Win32",
+                "/p:OutDir=out",
+                "/v:quiet",
+                "/m:4",
+                "/nr:false",
+                "/restore",
+                "@extra.rsp",
+                "/nologo",
+            ],
+            "The rendered arguments did not match the expected ones."
+        );
+    }
+
+    #[test]
+    fn test_msbuild_command_target_arch() {
+        let command = MsBuildCommand::new("msbuild.exe").target_arch(Arch::X86);
+        assert_eq!(command.to_args(), ["/p:Platform=Win32"]);
+        let command = MsBuildCommand::new("msbuild.exe").target_arch(
+            Arch::from_target_triple("aarch64-pc-windows-msvc")
+                .expect("It should be possible to parse the target triple."),
+        );
+        assert_eq!(command.to_args(), ["/p:Platform=ARM64"]);
+    }
+
+    #[test]
+    fn test_msbuild_command_escape_property_value() {
+        let command = MsBuildCommand::new("msbuild.exe").property("DefineConstants", "A;B,C");
+        assert_eq!(command.to_args(), ["/p:DefineConstants=A%3BB%2CC"]);
+
+        let command = MsBuildCommand::new("msbuild.exe").property("OutDir", "100%3B;done");
+        assert_eq!(command.to_args(), ["/p:OutDir=100%253B%3Bdone"]);
+    }
+
+    #[test]
+    fn test_msbuild_command_command_line() {
+        let command = MsBuildCommand::new("C:\\Program Files\\MsBuild\\msbuild.exe")
+            .project("my project.sln")
+            .max_cpu_count(None);
+        assert_eq!(
+            command.command_line(),
+            "\"C:\\Program Files\\MsBuild\\msbuild.exe\" \"my project.sln\" /m"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_msbuild_command_run_streaming() {
+        let command = MsBuildCommand::new("/bin/sh").args([
+            "-c",
+            "echo 'main.cpp(1,2): error C1: boom [app.vcxproj]'; echo done; exit 1",
+        ]);
+        let mut lines = Vec::new();
+        let error = command
+            .run_streaming(|_, line| lines.push(line.to_string()))
+            .expect_err("A failing command should result in an error.");
+        assert_eq!(
+            lines,
+            ["main.cpp(1,2): error C1: boom [app.vcxproj]", "done"]
+        );
+
+        let outcome = error
+            .build_outcome()
+            .expect("The error should contain the build outcome.");
+        assert_eq!(outcome.exit_code(), Some(1));
+        assert_eq!(outcome.errors().count(), 1);
+        assert!(outcome.stdout().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_msbuild_command_run() {
+        let outcome = MsBuildCommand::new("/bin/sh")
+            .args(["-c", "echo out; echo err >&2"])
+            .run()
+            .expect("A successful command should result in an outcome.");
+        assert!(outcome.success());
+        assert_eq!(outcome.stdout(), "out\n");
+        assert_eq!(outcome.stderr(), "err\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_msbuild_command_timeout() {
+        let error = MsBuildCommand::new("/bin/sh")
+            .args(["-c", "sleep 10"])
+            .timeout(Duration::from_millis(100))
+            .run()
+            .expect_err("The command should time out.");
+        assert!(matches!(error, Error::TimedOut { .. }));
+    }
+}
@@ -0,0 +1,309 @@
+//! Internal module for executing child processes while
+//! reading their output line by line.
+use crate::error::{Error, Result};
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The output stream that a line was read from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Handle for cancelling a running process from another thread.
+///
+/// Cloning the token results in a handle to the same cancellation
+/// state, so the token can be passed to the process while a clone is
+/// kept for cancelling it.
+///
+/// # Examples
+///
+/// ```
+/// use msbuild::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let handle = token.clone();
+/// std::thread::spawn(move || handle.cancel()).join().unwrap();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the cancellation of the processes using the token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether or not cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Limits on how long a child process is allowed to run.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProcessLimits {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+}
+
+impl ProcessLimits {
+    /// How often the limits are checked while waiting for output.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+    /// How long output is read after the process has exited.
+    const DRAIN_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+    // Internal function that returns an error if the
+    // process should be stopped.
+    fn check(&self, start: Instant) -> Result<()> {
+        if let Some(timeout) = self.timeout {
+            if start.elapsed() >= timeout {
+                return Err(Error::TimedOut { timeout });
+            }
+        }
+        if self
+            .cancellation_token
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            return Err(Error::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// Spawns the command and calls `on_line` for each line written to
+/// stdout or stderr as soon as it is available. The line is passed
+/// without the line ending.
+///
+/// The callback is executed on the calling thread.
+///
+/// If the limits are exceeded the process, and where possible its
+/// child processes, is killed and [`Error::TimedOut`] or, if cancelled,
+/// [`Error::Cancelled`] is returned. The limits only apply until the
+/// process has exited, output written after that, e.g. by processes it
+/// started, is read for at most [`ProcessLimits::DRAIN_GRACE_PERIOD`].
+pub(crate) fn run_with_lines(
+    command: &mut Command,
+    limits: &ProcessLimits,
+    mut on_line: impl FnMut(OutputStream, &str),
+) -> Result<ExitStatus> {
+    let start = Instant::now();
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (sender, receiver) = mpsc::channel::<(OutputStream, String)>();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|stdout| spawn_reader(stdout, OutputStream::Stdout, sender.clone())),
+        child
+            .stderr
+            .take()
+            .map(|stderr| spawn_reader(stderr, OutputStream::Stderr, sender.clone())),
+    ];
+    // The channel is closed when all the readers have finished.
+    drop(sender);
+
+    loop {
+        match receiver.recv_timeout(ProcessLimits::POLL_INTERVAL) {
+            Ok((stream, line)) => on_line(stream, line.as_str()),
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if let Some(status) = child.try_wait()? {
+            // Processes started by the child, e.g. reused msbuild
+            // nodes, can keep the pipes open after the child has
+            // exited so the readers are only waited for until they
+            // close the pipes or the grace period has passed. The child
+            // has been reaped so the limits no longer apply and nothing
+            // is killed since the process id may have been reused.
+            let exited = Instant::now();
+            while exited.elapsed() < ProcessLimits::DRAIN_GRACE_PERIOD {
+                match receiver.recv_timeout(ProcessLimits::POLL_INTERVAL) {
+                    Ok((stream, line)) => on_line(stream, line.as_str()),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            return Ok(status);
+        }
+        if let Err(e) = limits.check(start) {
+            kill_tree(&mut child);
+            return Err(e);
+        }
+    }
+
+    readers
+        .into_iter()
+        .flatten()
+        .try_for_each(|reader| reader.join().unwrap_or(Ok(())))?;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if let Err(e) = limits.check(start) {
+            kill_tree(&mut child);
+            return Err(e);
+        }
+        thread::sleep(ProcessLimits::POLL_INTERVAL);
+    }
+}
+
+/// Kills the process and, on Windows, all the processes it started.
+fn kill_tree(child: &mut Child) {
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", child.id().to_string().as_str()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Spawns a thread that reads lines from the output and sends
+/// them over the channel.
+fn spawn_reader(
+    output: impl Read + Send + 'static,
+    stream: OutputStream,
+    sender: Sender<(OutputStream, String)>,
+) -> thread::JoinHandle<std::io::Result<()>> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(output);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                return Ok(());
+            }
+            let line = String::from_utf8_lossy(&buffer);
+            let line = line.trim_end_matches(['\r', '\n']).to_string();
+            if sender.send((stream, line)).is_err() {
+                // The receiver is gone so there is no point in reading more.
+                return Ok(());
+            }
+        }
+    })
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_lines() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "printf 'a\\r\\nb\\n'; printf 'c' >&2; exit 3"]);
+        let mut lines = Vec::new();
+        let status = run_with_lines(&mut command, &ProcessLimits::default(), |stream, line| {
+            lines.push((stream, line.to_string()))
+        })
+        .expect("It should be possible to run the command.");
+        assert_eq!(status.code(), Some(3));
+
+        let stdout = lines
+            .iter()
+            .filter(|(s, _)| *s == OutputStream::Stdout)
+            .map(|(_, l)| l.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(stdout, ["a", "b"]);
+        assert!(lines.contains(&(OutputStream::Stderr, "c".to_string())));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_lines_timeout() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started; sleep 10"]);
+        let limits = ProcessLimits {
+            timeout: Some(Duration::from_millis(200)),
+            cancellation_token: None,
+        };
+        let start = Instant::now();
+        let error = run_with_lines(&mut command, &limits, |_, _| {})
+            .expect_err("The process should be killed when the timeout is reached.");
+        assert!(matches!(error, Error::TimedOut { .. }));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_lines_cancelled() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo started; sleep 10"]);
+        let token = CancellationToken::new();
+        let limits = ProcessLimits {
+            timeout: None,
+            cancellation_token: Some(token.clone()),
+        };
+        let error = run_with_lines(&mut command, &limits, |_, _| token.cancel())
+            .expect_err("The process should be killed when cancelled.");
+        assert!(matches!(error, Error::Cancelled));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_lines_grandchild_keeps_writing() {
+        // The grandchild keeps the pipes open and starts writing
+        // after the child has exited.
+        let script =
+            "(sleep 0.3; for i in $(seq 1 1000); do echo node; sleep 0.01; done) & echo done";
+        [
+            (None, None),
+            (Some(Duration::from_millis(200)), None),
+            (None, Some(CancellationToken::new())),
+        ]
+        .into_iter()
+        .for_each(|(timeout, cancellation_token)| {
+            let mut command = Command::new("sh");
+            command.args(["-c", script]);
+            let limits = ProcessLimits {
+                timeout,
+                cancellation_token: cancellation_token.clone(),
+            };
+            let start = Instant::now();
+            let mut lines = Vec::new();
+            let status = run_with_lines(&mut command, &limits, |_, line| {
+                if line == "node" {
+                    if let Some(token) = cancellation_token.as_ref() {
+                        token.cancel();
+                    }
+                }
+                lines.push(line.to_string());
+            })
+            .expect("The limits should not apply after the process has exited.");
+            assert!(status.success());
+            assert!(start.elapsed() < Duration::from_secs(5));
+            assert_eq!(lines.first().map(String::as_str), Some("done"));
+            assert!(lines.iter().any(|line| line == "node"));
+        });
+    }
+}
@@ -0,0 +1,731 @@
+//! Module containing the criteria used when selecting
+//! a VS installation.
+use crate::{versions::VsInstallationVersion, vs_installation::VsInstallation};
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+};
+
+/// The VS products, i.e. editions, that can be installed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VsProduct {
+    Community,
+    Professional,
+    Enterprise,
+    BuildTools,
+    /// Any other product identified by its full product id.
+    Other(String),
+}
+
+impl VsProduct {
+    const ID_PREFIX: &'static str = "Microsoft.VisualStudio.Product.";
+
+    /// The product id used by vswhere
+    /// e.g. `Microsoft.VisualStudio.Product.BuildTools`.
+    pub fn id(&self) -> String {
+        match self {
+            Self::Community => format!("{}Community", Self::ID_PREFIX),
+            Self::Professional => format!("{}Professional", Self::ID_PREFIX),
+            Self::Enterprise => format!("{}Enterprise", Self::ID_PREFIX),
+            Self::BuildTools => format!("{}BuildTools", Self::ID_PREFIX),
+            Self::Other(id) => id.clone(),
+        }
+    }
+
+    /// Creates the product from a product id reported by vswhere.
+    pub fn from_id(id: &str) -> Self {
+        match id.strip_prefix(Self::ID_PREFIX) {
+            Some("Community") => Self::Community,
+            Some("Professional") => Self::Professional,
+            Some("Enterprise") => Self::Enterprise,
+            Some("BuildTools") => Self::BuildTools,
+            _ => Self::Other(id.to_string()),
+        }
+    }
+
+    // Internal function for checking if the product id
+    // identifies this product.
+    fn matches(&self, product_id: &str) -> bool {
+        self.id().eq_ignore_ascii_case(product_id)
+    }
+}
+
+/// Criteria used when selecting a VS installation.
+///
+/// By default only stable installations that are complete and
+/// launchable are accepted.
+///
+/// Among the installations that fulfill the criteria the selected one
+/// is determined by the following order:
+/// 1. The most preferred product.
+/// 2. The highest installation version.
+/// 3. The lowest instance id, installations without an instance id last.
+/// 4. The lowest installation path.
+///
+/// So the same installation is selected regardless of the order in
+/// which the installations are reported.
+///
+/// # Examples
+///
+/// ```
+/// use msbuild::{InstallationCriteria, VsInstallationVersion, VsProduct};
+///
+/// // Prefer BuildTools over Enterprise and never use Community.
+/// let criteria = InstallationCriteria::new()
+///     .version_range(
+///         None,
+///         Some(VsInstallationVersion::parse("17.0").unwrap()),
+///     )
+///     .exclude_product(VsProduct::Community)
+///     .prefer_products([VsProduct::BuildTools, VsProduct::Enterprise]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct InstallationCriteria<'a> {
+    max: Option<VsInstallationVersion<'a>>,
+    min: Option<VsInstallationVersion<'a>>,
+    included_products: Vec<VsProduct>,
+    excluded_products: Vec<VsProduct>,
+    preferred_products: Vec<VsProduct>,
+    required_packages: Vec<String>,
+    requires_any: bool,
+    allow_prerelease: bool,
+    skip_incomplete: bool,
+    skip_non_launchable: bool,
+    instance_id: Option<String>,
+}
+
+impl Default for InstallationCriteria<'_> {
+    fn default() -> Self {
+        Self {
+            max: None,
+            min: None,
+            included_products: Vec::new(),
+            excluded_products: Vec::new(),
+            preferred_products: Vec::new(),
+            required_packages: Vec::new(),
+            requires_any: false,
+            allow_prerelease: false,
+            skip_incomplete: true,
+            skip_non_launchable: true,
+            instance_id: None,
+        }
+    }
+}
+
+/// The reason why an installation was rejected during selection.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum RejectionReason {
+    /// The instance data could not be interpreted.
+    InvalidInstance(String),
+    /// The installation version could not be parsed.
+    InvalidVersion(String),
+    /// The installation version is not in the requested range.
+    VersionOutOfRange(String),
+    /// The product of the installation was not accepted.
+    ProductNotAccepted(Option<String>),
+    /// The installation lacks the required workloads or components.
+    MissingPackages(Vec<String>),
+    /// Workloads or components are required but the instance does not
+    /// report its packages, e.g. a vswhere capture made without
+    /// `-include packages`.
+    PackagesUnknown,
+    /// The installation is a prerelease and prereleases
+    /// are not allowed.
+    Prerelease,
+    /// The installation is not complete.
+    Incomplete,
+    /// The installation can not be launched.
+    NotLaunchable,
+    /// The installation is not the pinned instance.
+    NotPinnedInstance,
+    /// The installation is not the one specified by the
+    /// `VS_INSTALLATION_PATH` environment variable.
+    NotSpecifiedPath,
+}
+
+/// The outcome of the selection for a candidate installation.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CandidateStatus {
+    /// The installation was selected.
+    Selected,
+    /// The installation fulfilled the criteria but another
+    /// installation was preferred.
+    Accepted,
+    /// The installation was rejected.
+    Rejected(RejectionReason),
+}
+
+/// A candidate installation that was considered during selection.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    path: PathBuf,
+    instance_id: Option<String>,
+    installation_version: Option<String>,
+    status: CandidateStatus,
+}
+
+impl Candidate {
+    /// Creates a candidate from an installation.
+    pub(crate) fn new(installation: &VsInstallation, status: CandidateStatus) -> Self {
+        Self {
+            path: installation.path().to_path_buf(),
+            instance_id: installation.instance_id().map(str::to_string),
+            installation_version: Some(installation.installation_version_str().to_string()),
+            status,
+        }
+    }
+
+    /// Creates a candidate from instance data that could not
+    /// be interpreted as an installation.
+    pub(crate) fn invalid(path: PathBuf, reason: String) -> Self {
+        Self {
+            path,
+            instance_id: None,
+            installation_version: None,
+            status: CandidateStatus::Rejected(RejectionReason::InvalidInstance(reason)),
+        }
+    }
+
+    /// The path of the installation. The path is empty if the
+    /// instance data did not contain any path.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// The instance id of the installation.
+    pub fn instance_id(&self) -> Option<&str> {
+        self.instance_id.as_deref()
+    }
+
+    /// The installation version as reported by vswhere.
+    pub fn installation_version(&self) -> Option<&str> {
+        self.installation_version.as_deref()
+    }
+
+    /// The outcome of the selection for the installation.
+    pub fn status(&self) -> &CandidateStatus {
+        &self.status
+    }
+}
+
+/// Report describing how each of the candidate installations
+/// was handled during selection.
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{InstallationCriteria, VsInstallation};
+///
+/// let report = VsInstallation::explain(&InstallationCriteria::new())
+///     .expect("vswhere should exist");
+/// println!("{}", report);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SelectionReport {
+    requested_range: String,
+    candidates: Vec<Candidate>,
+}
+
+impl SelectionReport {
+    /// Creates a report.
+    pub(crate) fn new(requested_range: String, candidates: Vec<Candidate>) -> Self {
+        Self {
+            requested_range,
+            candidates,
+        }
+    }
+
+    /// The requested version range e.g. `>=17.0.0, <18.0.0`.
+    pub fn requested_range(&self) -> &str {
+        self.requested_range.as_str()
+    }
+
+    /// All the candidates in the order they were reported.
+    pub fn candidates(&self) -> &[Candidate] {
+        self.candidates.as_slice()
+    }
+
+    /// The selected candidate if any.
+    pub fn selected(&self) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .find(|c| c.status == CandidateStatus::Selected)
+    }
+
+    /// The candidates that were rejected.
+    pub fn rejected(&self) -> impl Iterator<Item = &Candidate> {
+        self.candidates
+            .iter()
+            .filter(|c| matches!(c.status, CandidateStatus::Rejected(_)))
+    }
+}
+
+impl std::fmt::Display for SelectionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Version range {}:", self.requested_range)?;
+        if self.candidates.is_empty() {
+            write!(f, " no installations found")?;
+        }
+        self.candidates.iter().try_for_each(|c| {
+            let version = c.installation_version().unwrap_or("unknown version");
+            match &c.status {
+                CandidateStatus::Selected => {
+                    write!(f, "\n  selected {} ({})", c.path.display(), version)
+                }
+                CandidateStatus::Accepted => {
+                    write!(f, "\n  accepted {} ({})", c.path.display(), version)
+                }
+                CandidateStatus::Rejected(reason) => write!(
+                    f,
+                    "\n  rejected {} ({}): {}",
+                    c.path.display(),
+                    version,
+                    reason
+                ),
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidInstance(reason) => write!(f, "invalid instance data ({})", reason),
+            Self::InvalidVersion(reason) => write!(f, "invalid version ({})", reason),
+            Self::VersionOutOfRange(version) => {
+                write!(f, "version {} is not in the requested range", version)
+            }
+            Self::ProductNotAccepted(Some(product_id)) => {
+                write!(f, "product {} is not accepted", product_id)
+            }
+            Self::ProductNotAccepted(None) => write!(f, "product is unknown"),
+            Self::MissingPackages(ids) => write!(f, "missing {}", ids.join(", ")),
+            Self::PackagesUnknown => write!(f, "the packages of the installation are unknown"),
+            Self::Prerelease => write!(f, "prereleases are not allowed"),
+            Self::Incomplete => write!(f, "the installation is incomplete"),
+            Self::NotLaunchable => write!(f, "the installation is not launchable"),
+            Self::NotPinnedInstance => write!(f, "not the pinned instance"),
+            Self::NotSpecifiedPath => write!(f, "not the specified installation path"),
+        }
+    }
+}
+
+impl<'a> InstallationCriteria<'a> {
+    /// Creates criteria that accepts any stable, complete and
+    /// launchable installation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accept installations with a version in the range
+    /// between max (exclusive) and min (inclusive).
+    pub fn version_range(
+        mut self,
+        max: Option<VsInstallationVersion<'a>>,
+        min: Option<VsInstallationVersion<'a>>,
+    ) -> Self {
+        self.max = max;
+        self.min = min;
+        self
+    }
+
+    /// Only accept installations of the included products. If no
+    /// product has been included then all products are accepted.
+    pub fn include_product(mut self, product: VsProduct) -> Self {
+        self.included_products.push(product);
+        self
+    }
+
+    /// Never accept installations of the product.
+    pub fn exclude_product(mut self, product: VsProduct) -> Self {
+        self.excluded_products.push(product);
+        self
+    }
+
+    /// Sets the order in which products are preferred, the most
+    /// preferred first. Products not in the list are least preferred.
+    pub fn prefer_products(mut self, products: impl IntoIterator<Item = VsProduct>) -> Self {
+        self.preferred_products = products.into_iter().collect();
+        self
+    }
+
+    /// Only accept installations that contains the workload or
+    /// component with the id, e.g.
+    /// `Microsoft.VisualStudio.Component.VC.Tools.x86.x64`.
+    ///
+    /// This corresponds to the `-requires` argument of vswhere but the
+    /// requirements are checked against the packages reported by the
+    /// source, e.g. vswhere with `-include packages`, so the missing
+    /// packages of each rejected installation are known. Installations
+    /// that do not report their packages are rejected, except legacy
+    /// installations which do not have any packages.
+    pub fn require(mut self, package_id: impl Into<String>) -> Self {
+        self.required_packages.push(package_id.into());
+        self
+    }
+
+    /// Whether any, instead of all, of the required workloads and
+    /// components are sufficient. This corresponds to the `-requiresAny`
+    /// argument of vswhere.
+    pub fn requires_any(mut self, requires_any: bool) -> Self {
+        self.requires_any = requires_any;
+        self
+    }
+
+    /// Whether or not prerelease installations, e.g. previews,
+    /// are accepted. Defaults to false.
+    pub fn allow_prerelease(mut self, allow_prerelease: bool) -> Self {
+        self.allow_prerelease = allow_prerelease;
+        self
+    }
+
+    /// Whether or not installations that are not complete, e.g. because
+    /// an update was interrupted, are skipped. Defaults to true.
+    pub fn skip_incomplete(mut self, skip_incomplete: bool) -> Self {
+        self.skip_incomplete = skip_incomplete;
+        self
+    }
+
+    /// Whether or not installations that can not be launched
+    /// are skipped. Defaults to true.
+    pub fn skip_non_launchable(mut self, skip_non_launchable: bool) -> Self {
+        self.skip_non_launchable = skip_non_launchable;
+        self
+    }
+
+    /// Only accept the installation with the instance id. The
+    /// instance id is compared ignoring case.
+    pub fn instance_id(mut self, instance_id: impl Into<String>) -> Self {
+        self.instance_id = Some(instance_id.into());
+        self
+    }
+
+    /// The max version (exclusive).
+    pub fn max(&self) -> Option<&VsInstallationVersion<'a>> {
+        self.max.as_ref()
+    }
+
+    /// The min version (inclusive).
+    pub fn min(&self) -> Option<&VsInstallationVersion<'a>> {
+        self.min.as_ref()
+    }
+
+    /// Crate function for checking if the product of an
+    /// installation is accepted.
+    pub(crate) fn accepts_product(&self, product_id: Option<&str>) -> bool {
+        let is_included = self.included_products.is_empty()
+            || product_id.is_some_and(|id| self.included_products.iter().any(|p| p.matches(id)));
+        let is_excluded =
+            product_id.is_some_and(|id| self.excluded_products.iter().any(|p| p.matches(id)));
+        is_included && !is_excluded
+    }
+
+    /// Crate function for checking if any workloads or
+    /// components are required.
+    pub(crate) fn has_required_packages(&self) -> bool {
+        !self.required_packages.is_empty()
+    }
+
+    /// Crate function that returns the required workloads and
+    /// components that the installation is missing. If the requirements
+    /// are fulfilled then an empty collection is returned.
+    pub(crate) fn missing_packages(&self, installation: &VsInstallation) -> Vec<String> {
+        let missing = self
+            .required_packages
+            .iter()
+            .filter(|id| !installation.has_package(id))
+            .cloned()
+            .collect::<Vec<String>>();
+        if self.requires_any && missing.len() < self.required_packages.len() {
+            Vec::new()
+        } else {
+            missing
+        }
+    }
+
+    /// Crate function for checking the installation against the required
+    /// workloads and components. Legacy installations, which do not have
+    /// any packages, are accepted while other installations that does not
+    /// report their packages are rejected.
+    pub(crate) fn check_packages(
+        &self,
+        installation: &VsInstallation,
+    ) -> std::result::Result<(), RejectionReason> {
+        if self.required_packages.is_empty() {
+            return Ok(());
+        }
+        if !installation.reports_packages() {
+            return if installation.is_legacy() {
+                Ok(())
+            } else {
+                Err(RejectionReason::PackagesUnknown)
+            };
+        }
+        let missing = self.missing_packages(installation);
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(RejectionReason::MissingPackages(missing))
+        }
+    }
+
+    /// Crate function for checking the installation against the
+    /// prerelease, complete and launchable policy. Installations that
+    /// does not report the state, e.g. legacy installations, are accepted.
+    pub(crate) fn check_policy(
+        &self,
+        installation: &VsInstallation,
+    ) -> std::result::Result<(), RejectionReason> {
+        if !self.allow_prerelease && installation.is_prerelease() == Some(true) {
+            Err(RejectionReason::Prerelease)
+        } else if self.skip_incomplete && installation.is_complete() == Some(false) {
+            Err(RejectionReason::Incomplete)
+        } else if self.skip_non_launchable && installation.is_launchable() == Some(false) {
+            Err(RejectionReason::NotLaunchable)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Crate function for checking if the installation is
+    /// the pinned instance, if any.
+    pub(crate) fn accepts_instance(&self, instance_id: Option<&str>) -> bool {
+        self.instance_id
+            .as_deref()
+            .is_none_or(|pinned| instance_id.is_some_and(|id| id.eq_ignore_ascii_case(pinned)))
+    }
+
+    /// Crate function for ordering two accepted installations, the
+    /// preferred installation is ordered first.
+    pub(crate) fn compare(
+        &self,
+        (version_a, a): (&VsInstallationVersion, &VsInstallation),
+        (version_b, b): (&VsInstallationVersion, &VsInstallation),
+    ) -> Ordering {
+        self.product_rank(a.product_id())
+            .cmp(&self.product_rank(b.product_id()))
+            .then_with(|| version_b.cmp(version_a))
+            .then_with(|| match (a.instance_id(), b.instance_id()) {
+                (Some(id_a), Some(id_b)) => id_a.cmp(id_b),
+                (id_a, id_b) => id_b.is_some().cmp(&id_a.is_some()),
+            })
+            .then_with(|| a.path().cmp(b.path()))
+    }
+
+    /// Crate function for ranking the product of an installation,
+    /// a lower rank is more preferred.
+    pub(crate) fn product_rank(&self, product_id: Option<&str>) -> usize {
+        product_id
+            .and_then(|id| self.preferred_products.iter().position(|p| p.matches(id)))
+            .unwrap_or(self.preferred_products.len())
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const COMMUNITY: &str = "Microsoft.VisualStudio.Product.Community";
+    const ENTERPRISE: &str = "Microsoft.VisualStudio.Product.Enterprise";
+    const BUILD_TOOLS: &str = "Microsoft.VisualStudio.Product.BuildTools";
+
+    #[test]
+    fn test_vs_product_id() {
+        [
+            VsProduct::Community,
+            VsProduct::Professional,
+            VsProduct::Enterprise,
+            VsProduct::BuildTools,
+            VsProduct::Other("Microsoft.VisualStudio.Product.TeamExplorer".to_string()),
+        ]
+        .into_iter()
+        .for_each(|product| assert_eq!(VsProduct::from_id(product.id().as_str()), product));
+    }
+
+    #[test]
+    fn test_accepts_product() {
+        let criteria = InstallationCriteria::new();
+        assert!(criteria.accepts_product(Some(COMMUNITY)));
+        assert!(criteria.accepts_product(None));
+
+        let criteria = InstallationCriteria::new()
+            .include_product(VsProduct::Enterprise)
+            .include_product(VsProduct::BuildTools)
+            .exclude_product(VsProduct::BuildTools);
+        assert!(criteria.accepts_product(Some(ENTERPRISE)));
+        assert!(!criteria.accepts_product(Some(BUILD_TOOLS)));
+        assert!(!criteria.accepts_product(Some(COMMUNITY)));
+        assert!(
+            !criteria.accepts_product(None),
+            "Installations without a product id should not be accepted when products are included."
+        );
+    }
+
+    #[test]
+    fn test_missing_packages() {
+        let installation: VsInstallation = serde_json::from_value(serde_json::json!({
+            "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+            "installationVersion": "17.12.35506.116",
+            "packages": [
+                {
+                    "id": "Microsoft.VisualStudio.Component.VC.Tools.x86.x64",
+                    "version": "17.12.35504.99",
+                    "type": "Component"
+                },
+                {
+                    "id": "Microsoft.VisualStudio.Workload.NativeDesktop",
+                    "version": "17.12.35410.144",
+                    "type": "Workload"
+                }
+            ]
+        }))
+        .expect("It should be possible to deserialize the installation.");
+
+        let criteria = InstallationCriteria::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64")
+            .require("microsoft.visualstudio.workload.nativedesktop");
+        assert!(criteria.missing_packages(&installation).is_empty());
+
+        let criteria = criteria.require("Microsoft.VisualStudio.Component.VC.Tools.ARM64");
+        assert_eq!(
+            criteria.missing_packages(&installation),
+            ["Microsoft.VisualStudio.Component.VC.Tools.ARM64"]
+        );
+        assert!(criteria
+            .requires_any(true)
+            .missing_packages(&installation)
+            .is_empty());
+
+        let criteria = InstallationCriteria::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.ARM64")
+            .require("Microsoft.VisualStudio.Component.VC.Tools.ARM")
+            .requires_any(true);
+        assert_eq!(criteria.missing_packages(&installation).len(), 2);
+
+        let installation: VsInstallation = serde_json::from_value(serde_json::json!({
+            "installationPath": "C:\\Program Files (x86)\\Microsoft Visual Studio 14.0",
+            "installationVersion": "14.0",
+        }))
+        .expect("It should be possible to deserialize the installation.");
+        assert_eq!(
+            criteria.check_packages(&installation),
+            Ok(()),
+            "A legacy installation should not be rejected."
+        );
+
+        let installation: VsInstallation = serde_json::from_value(serde_json::json!({
+            "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\BuildTools",
+            "installationVersion": "17.12.35506.116",
+            "productId": "Microsoft.VisualStudio.Product.BuildTools",
+            "catalog": { "productLineVersion": "2022" },
+        }))
+        .expect("It should be possible to deserialize the installation.");
+        assert_eq!(
+            criteria.check_packages(&installation),
+            Err(RejectionReason::PackagesUnknown),
+            "An installation without packages should be rejected when packages are required."
+        );
+        assert_eq!(
+            InstallationCriteria::new().check_packages(&installation),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_check_policy() {
+        let installation = |prerelease: bool, complete: bool, launchable: bool| {
+            serde_json::from_value::<VsInstallation>(serde_json::json!({
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Preview",
+                "installationVersion": "17.13.35507.96",
+                "isPrerelease": prerelease,
+                "isComplete": complete,
+                "isLaunchable": launchable
+            }))
+            .expect("It should be possible to deserialize the installation.")
+        };
+        let criteria = InstallationCriteria::new();
+        assert_eq!(
+            criteria.check_policy(&installation(false, true, true)),
+            Ok(())
+        );
+        assert_eq!(
+            criteria.check_policy(&installation(true, true, true)),
+            Err(RejectionReason::Prerelease)
+        );
+        assert_eq!(
+            criteria.check_policy(&installation(false, false, true)),
+            Err(RejectionReason::Incomplete)
+        );
+        assert_eq!(
+            criteria.check_policy(&installation(false, true, false)),
+            Err(RejectionReason::NotLaunchable)
+        );
+
+        let criteria = InstallationCriteria::new()
+            .allow_prerelease(true)
+            .skip_incomplete(false)
+            .skip_non_launchable(false);
+        assert_eq!(
+            criteria.check_policy(&installation(true, false, false)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_accepts_instance() {
+        let criteria = InstallationCriteria::new();
+        assert!(criteria.accepts_instance(Some("019109ba")));
+        assert!(criteria.accepts_instance(None));
+
+        let criteria = InstallationCriteria::new().instance_id("019109BA");
+        assert!(criteria.accepts_instance(Some("019109ba")));
+        assert!(!criteria.accepts_instance(Some("4c9e2f1d")));
+        assert!(!criteria.accepts_instance(None));
+    }
+
+    #[test]
+    fn test_selection_report() {
+        let installation: VsInstallation = serde_json::from_value(serde_json::json!({
+            "installationPath": "C:\\VS\\2022\\BuildTools",
+            "installationVersion": "17.12.35506.116",
+        }))
+        .expect("It should be possible to deserialize the installation.");
+        let report = SelectionReport::new(
+            ">=17.0.0".to_string(),
+            vec![
+                Candidate::new(&installation, CandidateStatus::Selected),
+                Candidate::new(
+                    &installation,
+                    CandidateStatus::Rejected(RejectionReason::Prerelease),
+                ),
+                Candidate::invalid(PathBuf::new(), "missing field".to_string()),
+            ],
+        );
+        assert_eq!(
+            report.selected().map(|c| c.path()),
+            Some(Path::new("C:\\VS\\2022\\BuildTools"))
+        );
+        assert_eq!(report.rejected().count(), 2);
+        assert_eq!(
+            report.to_string(),
+            "Version range >=17.0.0:\n  \
+            selected C:\\VS\\2022\\BuildTools (17.12.35506.116)\n  \
+            rejected C:\\VS\\2022\\BuildTools (17.12.35506.116): prereleases are not allowed\n  \
+            rejected  (unknown version): invalid instance data (missing field)"
+        );
+    }
+
+    #[test]
+    fn test_product_rank() {
+        let criteria = InstallationCriteria::new()
+            .prefer_products([VsProduct::BuildTools, VsProduct::Enterprise]);
+        assert_eq!(criteria.product_rank(Some(BUILD_TOOLS)), 0);
+        assert_eq!(criteria.product_rank(Some(ENTERPRISE)), 1);
+        assert_eq!(criteria.product_rank(Some(COMMUNITY)), 2);
+        assert_eq!(criteria.product_rank(None), 2);
+    }
+}
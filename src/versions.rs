@@ -1,9 +1,7 @@
 //! Module containing code that handles versions.
+use crate::error::{Error, Result};
 use lenient_semver::Version;
-use std::{
-    convert::TryFrom,
-    io::{Error, ErrorKind},
-};
+use std::convert::TryFrom;
 
 /// Type used for specifying the version of the installation.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
@@ -11,13 +9,13 @@ pub struct VsInstallationVersion<'a>(Version<'a>);
 
 impl<'a> VsInstallationVersion<'a> {
     /// Parses the VsInstallationVersion from a string.
-    pub fn parse(value: &'a str) -> std::io::Result<VsInstallationVersion<'a>> {
+    pub fn parse(value: &'a str) -> Result<VsInstallationVersion<'a>> {
         Version::parse(value).map_or_else(
             |e| {
-                Err(Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Failed to parse &str as a VsInstallationVersion: {}", e),
-                ))
+                Err(Error::InvalidVersion {
+                    value: value.to_string(),
+                    reason: e.to_string(),
+                })
             },
             |v| Ok(VsInstallationVersion(v)),
         )
@@ -33,6 +31,23 @@ impl<'a> VsInstallationVersion<'a> {
     }
 }
 
+impl std::fmt::Display for VsInstallationVersion<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Crate function that describes a version range between max (exclusive)
+/// and min (inclusive) e.g. `>=17.0.0.0, <18.0.0.0`.
+pub(crate) fn describe_range<V: std::fmt::Display>(max: Option<&V>, min: Option<&V>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!(">={}, <{}", min, max),
+        (Some(min), None) => format!(">={}", min),
+        (None, Some(max)) => format!("<{}", max),
+        (None, None) => "*".to_string(),
+    }
+}
+
 /// Enum holding the VS product line versions.
 pub enum VsProductLineVersion {
     Vs2022,
@@ -43,7 +58,7 @@ pub enum VsProductLineVersion {
 impl VsProductLineVersion {
     /// The non inclusive max installation version for a
     /// specific product line version.
-    pub fn installation_version_max(&self) -> VsInstallationVersion {
+    pub fn installation_version_max(&self) -> VsInstallationVersion<'_> {
         // Constant values that are always safe to parse.
         match self {
             Self::Vs2022 => VsInstallationVersion::parse("18.0.0.0").unwrap(),
@@ -54,7 +69,7 @@ impl VsProductLineVersion {
 
     /// The inclusive min installation version for a
     /// specific product line version.
-    pub fn installation_version_min(&self) -> VsInstallationVersion {
+    pub fn installation_version_min(&self) -> VsInstallationVersion<'_> {
         match self {
             Self::Vs2022 => VsInstallationVersion::parse("17.0.0.0").unwrap(),
             Self::Vs2019 => VsInstallationVersion::parse("16.0.0.0").unwrap(),
@@ -66,15 +81,12 @@ impl VsProductLineVersion {
 impl TryFrom<&str> for VsProductLineVersion {
     type Error = Error;
 
-    fn try_from(s: &str) -> std::io::Result<Self> {
+    fn try_from(s: &str) -> Result<Self> {
         match s {
             "2017" => Ok(VsProductLineVersion::Vs2017),
             "2019" => Ok(VsProductLineVersion::Vs2019),
             "2022" => Ok(VsProductLineVersion::Vs2022),
-            _ => Err(Error::new(
-                ErrorKind::InvalidData,
-                format!("Product line version {} did not match any known values.", s),
-            )),
+            _ => Err(Error::UnknownProductLineVersion(s.to_string())),
         }
     }
 }
@@ -84,13 +96,13 @@ impl TryFrom<&str> for VsProductLineVersion {
 pub struct WinSdkVersion<'a>(Version<'a>);
 
 impl<'a> WinSdkVersion<'a> {
-    pub fn parse(value: &'a str) -> std::io::Result<WinSdkVersion<'a>> {
+    pub fn parse(value: &'a str) -> Result<WinSdkVersion<'a>> {
         Version::parse(value).map_or_else(
             |e| {
-                Err(Error::new(
-                    ErrorKind::InvalidData,
-                    format!("Failed to parse &str as a WinSdkVersion: {}", e),
-                ))
+                Err(Error::InvalidVersion {
+                    value: value.to_string(),
+                    reason: e.to_string(),
+                })
             },
             |v| Ok(WinSdkVersion(v)),
         )
@@ -106,11 +118,45 @@ impl<'a> WinSdkVersion<'a> {
     }
 }
 
+/// The version of a MSVC toolset e.g. `14.42.34433`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct MsvcToolsetVersion<'a>(Version<'a>);
+
+impl<'a> MsvcToolsetVersion<'a> {
+    /// Parses the MsvcToolsetVersion from a string.
+    pub fn parse(value: &'a str) -> Result<MsvcToolsetVersion<'a>> {
+        Version::parse(value).map_or_else(
+            |e| {
+                Err(Error::InvalidVersion {
+                    value: value.to_string(),
+                    reason: e.to_string(),
+                })
+            },
+            |v| Ok(MsvcToolsetVersion(v)),
+        )
+    }
+
+    /// Crate function for checking if the version is in the specified range.
+    pub(crate) fn is_in_range(
+        &self,
+        max: Option<&MsvcToolsetVersion>,
+        min: Option<&MsvcToolsetVersion>,
+    ) -> bool {
+        has_version_in_range(&self.0, max.map(|v| &v.0), min.map(|v| &v.0))
+    }
+}
+
+impl std::fmt::Display for MsvcToolsetVersion<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 /// Internal function to check if a version is in the range
 /// if it has been specified.
 fn has_version_in_range(version: &Version, max: Option<&Version>, min: Option<&Version>) -> bool {
-    let is_below_max: bool = max.map_or(true, |max_version| max_version > version);
-    let is_above_min: bool = min.map_or(true, |min_version| version >= min_version);
+    let is_below_max: bool = max.is_none_or(|max_version| max_version > version);
+    let is_above_min: bool = min.is_none_or(|min_version| version >= min_version);
     is_below_max && is_above_min
 }
 
@@ -198,4 +244,16 @@ mod test {
             "The version 4.3.2.11 should not be in range when max is 4.3.2.1 and no max is given."
         );
     }
+
+    #[test]
+    fn test_describe_range() {
+        let max = VsInstallationVersion::parse("18.0")
+            .expect("It should be possible to parse the 18.0 as a version.");
+        let min = VsInstallationVersion::parse("17.0")
+            .expect("It should be possible to parse the 17.0 as a version.");
+        assert_eq!(describe_range(Some(&max), Some(&min)), ">=17.0.0, <18.0.0");
+        assert_eq!(describe_range(None, Some(&min)), ">=17.0.0");
+        assert_eq!(describe_range(Some(&max), None), "<18.0.0");
+        assert_eq!(describe_range::<VsInstallationVersion>(None, None), "*");
+    }
 }
@@ -0,0 +1,298 @@
+//! Module for capturing the environment of the VS developer
+//! command prompt by running the scripts of the installation.
+use crate::{
+    arch::{Arch, HostTarget},
+    error::{Error, Result},
+    process::{self, CancellationToken, OutputStream, ProcessLimits},
+    vs_dev_env::VsDevEnv,
+    VsInstallation,
+};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+/// Type for running `vcvarsall.bat` or `VsDevCmd.bat` and capturing
+/// the environment variables that the script sets.
+///
+/// This requires Windows and is mostly useful as a cross-check of
+/// the environment computed by [`VsDevEnv::compute`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{Arch, HostTarget, InstallationCriteria, VsDevCmd, VsDevEnv, VsInstallation};
+///
+/// let installation = VsInstallation::find(&InstallationCriteria::new())
+///     .expect("A VS installation should exist");
+/// let host_target = HostTarget::native(Arch::X64);
+/// let captured = VsDevCmd::vcvarsall(&installation, host_target)
+///     .and_then(|vcvarsall| vcvarsall.run())
+///     .expect("vcvarsall.bat should succeed");
+/// let computed = VsDevEnv::find(&installation, host_target)
+///     .expect("The environment should be computed");
+/// assert_eq!(captured.get("VCToolsVersion"), computed.get("VCToolsVersion"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct VsDevCmd {
+    script: PathBuf,
+    args: Vec<String>,
+    limits: ProcessLimits,
+}
+
+impl VsDevCmd {
+    const VCVARSALL: &'static str = "VC/Auxiliary/Build/vcvarsall.bat";
+    const VS_DEV_CMD: &'static str = "Common7/Tools/VsDevCmd.bat";
+    const COMSPEC_ENV_KEY: &'static str = "ComSpec";
+    const MARKER: &'static str = "__MSBUILD_ENV_MARKER__";
+
+    /// Creates a VsDevCmd object for the `VC/Auxiliary/Build/vcvarsall.bat`
+    /// script of the installation with the arguments for the tools that
+    /// run on the host and produce binaries for the target, e.g. `x64_arm64`.
+    pub fn vcvarsall(vs_installation: &VsInstallation, host_target: HostTarget) -> Result<Self> {
+        Self::script(vs_installation.path(), Self::VCVARSALL).map(|script| Self {
+            script,
+            args: vec![host_target.to_string()],
+            limits: ProcessLimits::default(),
+        })
+    }
+
+    /// Creates a VsDevCmd object for the `Common7/Tools/VsDevCmd.bat`
+    /// script of the installation with the arguments for the tools that
+    /// run on the host and produce binaries for the target.
+    pub fn dev_cmd(vs_installation: &VsInstallation, host_target: HostTarget) -> Result<Self> {
+        Self::script(vs_installation.path(), Self::VS_DEV_CMD).map(|script| Self {
+            script,
+            args: vec![
+                format!("-arch={}", Self::vs_dev_cmd_arch(host_target.target())),
+                format!("-host_arch={}", Self::vs_dev_cmd_arch(host_target.host())),
+                "-no_logo".to_string(),
+            ],
+            limits: ProcessLimits::default(),
+        })
+    }
+
+    /// Sets the maximum time the script is allowed to run.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a token that can be used in order to cancel
+    /// the script from another thread.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.limits.cancellation_token = Some(token);
+        self
+    }
+
+    /// The path of the script.
+    pub fn script_path(&self) -> &Path {
+        self.script.as_path()
+    }
+
+    /// The arguments passed to the script.
+    pub fn args(&self) -> &[String] {
+        self.args.as_slice()
+    }
+
+    /// Runs the script in `cmd.exe` and returns the environment
+    /// variables that were added or changed by the script.
+    pub fn run(&self) -> Result<VsDevEnv> {
+        let script = self.command_line();
+        let comspec =
+            std::env::var_os(Self::COMSPEC_ENV_KEY).unwrap_or_else(|| OsString::from("cmd.exe"));
+        debug!("Running {:?} /d /s /c \"{}\"", comspec, script);
+        let mut command = Command::new(comspec);
+        command.args(["/d", "/s", "/c"]);
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            command.raw_arg(format!("\"{}\"", script));
+        }
+        #[cfg(not(windows))]
+        command.arg(script);
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let status = process::run_with_lines(&mut command, &self.limits, |stream, line| {
+            let output = match stream {
+                OutputStream::Stdout => &mut stdout,
+                OutputStream::Stderr => &mut stderr,
+            };
+            output.push_str(line);
+            output.push('\n');
+        })?;
+        debug!("{} exited with {}", self.script.display(), status);
+        self.parse_output(stdout.as_str()).map_err(|e| match e {
+            Error::ScriptFailed { script, reason } if !stderr.trim().is_empty() => {
+                Error::ScriptFailed {
+                    script,
+                    reason: format!("{}\n{}", reason, stderr.trim()),
+                }
+            }
+            e => e,
+        })
+    }
+
+    // Internal function that returns the command line that prints the
+    // environment before and after calling the script separated by the
+    // marker. The second marker is only printed if the script succeeds.
+    fn command_line(&self) -> String {
+        format!(
+            "set && echo {marker} && call \"{script}\" {args} && echo {marker} && set",
+            marker = Self::MARKER,
+            script = self.script.display(),
+            args = self.args.join(" ")
+        )
+    }
+
+    // Internal function that creates the environment from the
+    // output of the command line.
+    fn parse_output(&self, output: &str) -> Result<VsDevEnv> {
+        let sections =
+            output
+                .split_inclusive('\n')
+                .fold(vec![String::new()], |mut sections, line| {
+                    if line.trim() == Self::MARKER {
+                        sections.push(String::new());
+                    } else if let Some(section) = sections.last_mut() {
+                        section.push_str(line);
+                    }
+                    sections
+                });
+        match sections.as_slice() {
+            [before, _, after] => Ok(VsDevEnv::from_set_output(before, after)),
+            _ => Err(Error::ScriptFailed {
+                script: self.script.clone(),
+                reason: sections
+                    .get(1)
+                    .map(|script_output| script_output.trim().to_string())
+                    .filter(|script_output| !script_output.is_empty())
+                    .unwrap_or_else(|| "The script did not complete.".to_string()),
+            }),
+        }
+    }
+
+    // Internal function that returns the verified path of the script.
+    fn script(install_dir: &Path, file: &str) -> Result<PathBuf> {
+        let path = install_dir.join(file);
+        trace!("Probing for the script at {}", path.display());
+        if !path.is_file() {
+            return Err(Error::MissingComponentFile {
+                parent: install_dir.to_path_buf(),
+                file: file.to_string(),
+            });
+        }
+        Ok(path)
+    }
+
+    // Internal function that returns the architecture name used
+    // by the arguments of `VsDevCmd.bat`.
+    fn vs_dev_cmd_arch(arch: Arch) -> &'static str {
+        match arch {
+            Arch::X64 => "amd64",
+            arch => arch.dir_name(),
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    // Helper that creates an installation containing the scripts.
+    fn installation_with_scripts() -> (TempDir, VsInstallation) {
+        let root = tempdir().expect("It should be possible to create a temporary directory.");
+        [VsDevCmd::VCVARSALL, VsDevCmd::VS_DEV_CMD]
+            .iter()
+            .for_each(|file| {
+                let path = root.path().join(file);
+                std::fs::create_dir_all(path.parent().unwrap())
+                    .expect("It should be possible to create the directories.");
+                std::fs::write(path, "@echo off")
+                    .expect("It should be possible to create the file.");
+            });
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": root.path(),
+            "installationVersion": "17.12.35527.113",
+        }))
+        .expect("It should be possible to create the installation.");
+        (root, installation)
+    }
+
+    #[test]
+    fn test_args() {
+        let (root, installation) = installation_with_scripts();
+
+        let vcvarsall = VsDevCmd::vcvarsall(&installation, HostTarget::new(Arch::X64, Arch::Arm64))
+            .expect("It should be possible to find vcvarsall.bat.");
+        assert_eq!(
+            vcvarsall.script_path(),
+            root.path().join(VsDevCmd::VCVARSALL)
+        );
+        assert_eq!(vcvarsall.args(), ["x64_arm64"]);
+        assert_eq!(
+            VsDevCmd::vcvarsall(&installation, HostTarget::native(Arch::X86))
+                .expect("It should be possible to find vcvarsall.bat.")
+                .args(),
+            ["x86"]
+        );
+
+        let vs_dev_cmd = VsDevCmd::dev_cmd(&installation, HostTarget::new(Arch::X64, Arch::X86))
+            .expect("It should be possible to find VsDevCmd.bat.");
+        assert_eq!(
+            vs_dev_cmd.args(),
+            ["-arch=x86", "-host_arch=amd64", "-no_logo"]
+        );
+        assert!(vs_dev_cmd.command_line().contains(&format!(
+            "call \"{}\" -arch=x86 -host_arch=amd64 -no_logo",
+            root.path().join(VsDevCmd::VS_DEV_CMD).display()
+        )));
+
+        std::fs::remove_file(root.path().join(VsDevCmd::VCVARSALL))
+            .expect("It should be possible to remove the file.");
+        assert!(matches!(
+            VsDevCmd::vcvarsall(&installation, HostTarget::native(Arch::X64)),
+            Err(Error::MissingComponentFile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let (_root, installation) = installation_with_scripts();
+        let vcvarsall = VsDevCmd::vcvarsall(&installation, HostTarget::native(Arch::X64))
+            .expect("It should be possible to find vcvarsall.bat.");
+
+        let output = "Path=C:\\Windows\r\n\
+                      __MSBUILD_ENV_MARKER__ \r\n\
+                      **********************************************************************\r\n\
+                      ** Visual Studio 2022 Developer Command Prompt v17.12.3\r\n\
+                      **********************************************************************\r\n\
+                      [vcvarsall.bat] Environment initialized for: 'x64'\r\n\
+                      __MSBUILD_ENV_MARKER__ \r\n\
+                      Path=C:\\VS\\bin\\Hostx64\\x64;C:\\Windows\r\n\
+                      VSCMD_ARG_TGT_ARCH=x64\r\n";
+        let env = vcvarsall
+            .parse_output(output)
+            .expect("It should be possible to parse the output.");
+        assert_eq!(env.get("PATH"), Some("C:\\VS\\bin\\Hostx64\\x64"));
+        assert_eq!(env.get("VSCMD_ARG_TGT_ARCH"), Some("x64"));
+
+        let output = "Path=C:\\Windows\r\n\
+                      __MSBUILD_ENV_MARKER__ \r\n\
+                      [ERROR:vcvarsall.bat] Invalid argument found : x65\r\n";
+        let error = vcvarsall
+            .parse_output(output)
+            .expect_err("The output of a failed script should not be parsed.");
+        assert!(matches!(
+            error,
+            Error::ScriptFailed { reason, .. } if reason == "[ERROR:vcvarsall.bat] Invalid argument found : x65"
+        ));
+    }
+}
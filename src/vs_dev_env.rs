@@ -0,0 +1,547 @@
+//! Module for composing the environment of the
+//! VS developer command prompt.
+use crate::{
+    arch::HostTarget, error::Result, vs_msvc_toolset::VsMsvcToolset, win_sdk::WinSdk,
+    VsInstallation,
+};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Type holding the environment variables that are needed in order
+/// to run the compiler tools, e.g. `cl.exe` and `link.exe`, directly.
+///
+/// The environment corresponds to the one `vcvarsall.bat` produces
+/// but is computed from the directory layout of the VS installation,
+/// the MSVC toolset and the Windows SDK. The list variables, i.e.
+/// `INCLUDE`, `LIB`, `LIBPATH` and `PATH`, only contain the added
+/// directories separated by `;`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{HostTarget, InstallationCriteria, VsDevEnv, VsInstallation};
+/// use std::process::Command;
+///
+/// let installation = VsInstallation::find(&InstallationCriteria::new())
+///     .expect("A VS installation should exist");
+/// // Target the architecture of the build script, i.e. `TARGET` set by cargo.
+/// let host_target = HostTarget::from_target_triple("x86_64-pc-windows-msvc")
+///     .expect("The target should be known");
+/// let env = VsDevEnv::find(&installation, host_target)
+///     .expect("The toolset and the Windows SDK should exist");
+/// let mut command = Command::new("cl.exe");
+/// env.apply(&mut command);
+/// command.arg("main.c");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VsDevEnv {
+    vars: BTreeMap<String, String>,
+}
+
+impl VsDevEnv {
+    const SEPARATOR: &'static str = ";";
+    const LIST_VARS: [&'static str; 4] = ["INCLUDE", "LIB", "LIBPATH", "PATH"];
+
+    /// Computes the environment using the default MSVC toolset of the
+    /// installation and the latest Windows SDK.
+    pub fn find(vs_installation: &VsInstallation, host_target: HostTarget) -> Result<Self> {
+        let toolset = VsMsvcToolset::try_from(vs_installation)?;
+        let win_sdk = WinSdk::find()?;
+        Self::compute(vs_installation, &toolset, &win_sdk, host_target)
+    }
+
+    /// Computes the environment for the tools running on the host and
+    /// producing binaries for the target.
+    ///
+    /// The directories of the toolset and the Windows SDK that the
+    /// tools can not work without must exist while optional directories,
+    /// e.g. the ATL and MFC directories, are only added if they exist.
+    pub fn compute(
+        vs_installation: &VsInstallation,
+        toolset: &VsMsvcToolset,
+        win_sdk: &WinSdk,
+        host_target: HostTarget,
+    ) -> Result<Self> {
+        let (host, target) = (host_target.host(), host_target.target());
+        let install_dir = vs_installation.path();
+        let vc_dir = install_dir.join("VC");
+        let sdk_includes = win_sdk.include_dirs();
+
+        let include = [
+            Some(toolset.include()?),
+            toolset.atlmfc_include().ok(),
+            Self::optional_dir(vc_dir.join("Auxiliary/VS/include")),
+            Some(sdk_includes.ucrt_dir().to_path_buf()),
+            Some(sdk_includes.um_dir().to_path_buf()),
+            Some(sdk_includes.shared_dir().to_path_buf()),
+            Some(sdk_includes.winrt_dir().to_path_buf()),
+            Some(sdk_includes.cppwinrt_dir().to_path_buf()),
+        ];
+        let lib = [
+            toolset.atlmfc_lib(target).ok(),
+            Some(toolset.lib(target)?),
+            Self::optional_dir(vc_dir.join("Auxiliary/VS/lib").join(target.dir_name())),
+            Some(win_sdk.ucrt_lib_dir(target)?),
+            Some(win_sdk.um_lib_dir(target)?),
+        ];
+        let lib_path = [
+            toolset.atlmfc_lib(target).ok(),
+            Some(toolset.lib(target)?),
+            Self::optional_dir(toolset.dir().join("lib/x86/store/references")),
+            win_sdk.union_metadata_dir().ok(),
+            win_sdk.references_dir().ok(),
+        ];
+        let path = [
+            Some(toolset.bin(host_target)?),
+            // The cross compilers depend on the DLLs of the host tools.
+            if host_target.is_cross() {
+                toolset.bin(HostTarget::native(host)).ok()
+            } else {
+                None
+            },
+            win_sdk.bin_dir(host).ok(),
+            Self::optional_dir(install_dir.join("MSBuild/Current/Bin")),
+            Self::optional_dir(install_dir.join("Common7/IDE")),
+            Self::optional_dir(install_dir.join("Common7/Tools")),
+        ];
+
+        let mut vars = BTreeMap::new();
+        let mut insert = |key: &str, value: String| {
+            vars.insert(key.to_string(), value);
+        };
+        insert("INCLUDE", Self::join(&include));
+        insert("LIB", Self::join(&lib));
+        insert("LIBPATH", Self::join(&lib_path));
+        insert("PATH", Self::join(&path));
+        insert("VSINSTALLDIR", Self::dir_value(install_dir));
+        insert("VCINSTALLDIR", Self::dir_value(vc_dir.as_path()));
+        insert("VCToolsInstallDir", Self::dir_value(toolset.dir()));
+        insert("VCToolsVersion", toolset.version().to_string());
+        insert("WindowsSdkDir", Self::dir_value(win_sdk.dir()));
+        insert("WindowsSDKVersion", format!("{}\\", win_sdk.version()));
+        insert("WindowsSDKLibVersion", format!("{}\\", win_sdk.version()));
+        insert(
+            "WindowsSdkBinPath",
+            Self::dir_value(win_sdk.dir().join("bin").as_path()),
+        );
+        insert(
+            "WindowsSdkVerBinPath",
+            Self::dir_value(win_sdk.dir().join("bin").join(win_sdk.version()).as_path()),
+        );
+        insert("UniversalCRTSdkDir", Self::dir_value(win_sdk.dir()));
+        insert("UCRTVersion", win_sdk.version().to_string());
+        insert("VSCMD_ARG_HOST_ARCH", host.dir_name().to_string());
+        insert("VSCMD_ARG_TGT_ARCH", target.dir_name().to_string());
+        Ok(Self { vars })
+    }
+
+    /// Creates the environment from the difference between the output
+    /// of the `set` command before and after running a script, e.g.
+    /// `vcvarsall.bat`. Variables that are unchanged or removed are not
+    /// included and for the list variables only the directories added
+    /// in front of the previous value are included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use msbuild::VsDevEnv;
+    ///
+    /// let before = "Path=C:\\Windows\r\nUSERNAME=builder\r\n";
+    /// let after = "Path=C:\\VS\\bin;C:\\Windows\r\nUSERNAME=builder\r\nVCToolsVersion=14.42.34433\r\n";
+    /// let env = VsDevEnv::from_set_output(before, after);
+    /// assert_eq!(env.get("PATH"), Some("C:\\VS\\bin"));
+    /// assert_eq!(env.get("VCToolsVersion"), Some("14.42.34433"));
+    /// assert_eq!(env.get("USERNAME"), None);
+    /// ```
+    pub fn from_set_output(before: &str, after: &str) -> Self {
+        let before = Self::parse_set_output(before)
+            .into_iter()
+            .map(|(key, value)| (key.to_ascii_uppercase(), value))
+            .collect::<BTreeMap<String, String>>();
+        let vars = Self::parse_set_output(after)
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let previous = before.get(&key.to_ascii_uppercase());
+                if previous == Some(&value) {
+                    return None;
+                }
+                let is_list = Self::LIST_VARS
+                    .iter()
+                    .any(|list_var| list_var.eq_ignore_ascii_case(key.as_str()));
+                let value = match previous {
+                    Some(previous) if is_list && !previous.is_empty() => value
+                        .strip_suffix(previous.as_str())
+                        .map(|added| added.trim_end_matches(Self::SEPARATOR).to_string())
+                        .unwrap_or(value),
+                    _ => value,
+                };
+                // The list variables use the same names as the computed environment.
+                let key = if is_list {
+                    key.to_ascii_uppercase()
+                } else {
+                    key
+                };
+                Some((key, value))
+            })
+            .collect();
+        Self { vars }
+    }
+
+    /// The environment variables ordered by name.
+    pub fn vars(&self) -> &BTreeMap<String, String> {
+        &self.vars
+    }
+
+    /// The value of the environment variable.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(String::as_str)
+    }
+
+    /// The directories of a list variable, e.g. `PATH`.
+    pub fn paths(&self, key: &str) -> Vec<PathBuf> {
+        self.get(key)
+            .map(|value| {
+                value
+                    .split(Self::SEPARATOR)
+                    .filter(|s| !s.is_empty())
+                    .map(PathBuf::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Sets the environment variables of the command. The directories
+    /// of the list variables are prepended to the value the command
+    /// would otherwise inherit.
+    pub fn apply<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        self.vars.iter().for_each(|(key, value)| {
+            let inherited = Self::LIST_VARS
+                .contains(&key.as_str())
+                .then(|| Self::inherited_value(command, key))
+                .flatten()
+                .filter(|inherited| !inherited.is_empty());
+            match inherited {
+                Some(inherited) => {
+                    let mut combined = OsString::from(value);
+                    combined.push(Self::SEPARATOR);
+                    combined.push(inherited);
+                    command.env(key, combined)
+                }
+                None => command.env(key, value),
+            };
+        });
+        command
+    }
+
+    // Internal function that parses the output of the `set` command
+    // i.e. one `NAME=value` line for each variable. The hidden
+    // variables, e.g. `=C:=C:\`, are ignored.
+    fn parse_set_output(output: &str) -> Vec<(String, String)> {
+        output
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.starts_with('='))
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    // Internal function that returns the value of the environment variable
+    // the command would inherit, i.e. the value set on the command or the
+    // value of the current process.
+    fn inherited_value(command: &Command, key: &str) -> Option<OsString> {
+        command
+            .get_envs()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.map(OsString::from))
+            .unwrap_or_else(|| std::env::var_os(key))
+    }
+
+    // Internal function that returns the directory if it exists.
+    fn optional_dir(dir: PathBuf) -> Option<PathBuf> {
+        dir.is_dir().then_some(dir)
+    }
+
+    // Internal function that joins the existing directories
+    // into the value of a list variable.
+    fn join(dirs: &[Option<PathBuf>]) -> String {
+        dirs.iter()
+            .flatten()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .collect::<Vec<String>>()
+            .join(Self::SEPARATOR)
+    }
+
+    // Internal function that formats a directory the way vcvarsall
+    // does i.e. with a trailing separator.
+    fn dir_value(dir: &Path) -> String {
+        let value = dir.to_string_lossy();
+        if value.ends_with(['\\', '/']) {
+            value.into_owned()
+        } else {
+            format!("{}{}", value, std::path::MAIN_SEPARATOR)
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a VsDevEnv {
+    type Item = (&'a String, &'a String);
+    type IntoIter = std::collections::btree_map::Iter<'a, String, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vars.iter()
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arch::Arch;
+    use tempfile::{tempdir, TempDir};
+
+    // Helper that creates the directories inside the root.
+    fn create_dirs(root: &Path, dirs: &[&str]) {
+        dirs.iter().for_each(|dir| {
+            std::fs::create_dir_all(root.join(dir))
+                .unwrap_or_else(|_| panic!("It should be possible to create the dir {}", dir))
+        });
+    }
+
+    // Helper that creates a fixture tree with an installation
+    // containing a toolset and a Windows SDK.
+    fn fixture() -> (TempDir, VsInstallation, VsMsvcToolset, WinSdk) {
+        let root = tempdir().expect("It should be possible to create a temporary directory.");
+        let vs = root.path().join("VS");
+        let sdk = root.path().join("Windows Kits/10");
+        create_dirs(
+            vs.as_path(),
+            &[
+                "VC/Tools/MSVC/14.42.34433/bin/Hostx64/x64",
+                "VC/Tools/MSVC/14.42.34433/bin/Hostx64/arm64",
+                "VC/Tools/MSVC/14.42.34433/include",
+                "VC/Tools/MSVC/14.42.34433/lib/x64",
+                "VC/Tools/MSVC/14.42.34433/lib/arm64",
+                "VC/Tools/MSVC/14.42.34433/atlmfc/include",
+                "VC/Tools/MSVC/14.42.34433/atlmfc/lib/x64",
+                "MSBuild/Current/Bin",
+            ],
+        );
+        create_dirs(
+            sdk.as_path(),
+            &[
+                "Include/10.0.22621.0/cppwinrt",
+                "Include/10.0.22621.0/shared",
+                "Include/10.0.22621.0/ucrt",
+                "Include/10.0.22621.0/um",
+                "Include/10.0.22621.0/winrt",
+                "Lib/10.0.22621.0/ucrt/x64",
+                "Lib/10.0.22621.0/um/x64",
+                "Lib/10.0.22621.0/ucrt/arm64",
+                "Lib/10.0.22621.0/um/arm64",
+                "bin/10.0.22621.0/x64",
+                "UnionMetadata/10.0.22621.0",
+            ],
+        );
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": vs,
+            "installationVersion": "17.12.35527.113",
+        }))
+        .expect("It should be possible to create the installation.");
+        let toolset = VsMsvcToolset::try_from(&installation)
+            .expect("It should be possible to find the toolset.");
+        let win_sdk = WinSdk::find_in_dir(sdk.as_path(), None, None)
+            .expect("It should be possible to find the Windows SDK.");
+        (root, installation, toolset, win_sdk)
+    }
+
+    #[test]
+    fn test_compute() {
+        let (root, installation, toolset, win_sdk) = fixture();
+        let vs = root.path().join("VS");
+        let msvc = vs.join("VC/Tools/MSVC/14.42.34433");
+        let sdk = root.path().join("Windows Kits/10");
+
+        let env = VsDevEnv::compute(
+            &installation,
+            &toolset,
+            &win_sdk,
+            HostTarget::native(Arch::X64),
+        )
+        .expect("It should be possible to compute the environment.");
+        assert_eq!(
+            env.paths("INCLUDE"),
+            [
+                msvc.join("include"),
+                msvc.join("atlmfc/include"),
+                sdk.join("Include/10.0.22621.0/ucrt"),
+                sdk.join("Include/10.0.22621.0/um"),
+                sdk.join("Include/10.0.22621.0/shared"),
+                sdk.join("Include/10.0.22621.0/winrt"),
+                sdk.join("Include/10.0.22621.0/cppwinrt"),
+            ]
+        );
+        assert_eq!(
+            env.paths("LIB"),
+            [
+                msvc.join("atlmfc/lib/x64"),
+                msvc.join("lib/x64"),
+                sdk.join("Lib/10.0.22621.0/ucrt/x64"),
+                sdk.join("Lib/10.0.22621.0/um/x64"),
+            ]
+        );
+        assert_eq!(
+            env.paths("LIBPATH"),
+            [
+                msvc.join("atlmfc/lib/x64"),
+                msvc.join("lib/x64"),
+                sdk.join("UnionMetadata/10.0.22621.0"),
+            ]
+        );
+        assert_eq!(
+            env.paths("PATH"),
+            [
+                msvc.join("bin/Hostx64/x64"),
+                sdk.join("bin/10.0.22621.0/x64"),
+                vs.join("MSBuild/Current/Bin"),
+            ]
+        );
+        assert_eq!(env.get("VCToolsVersion"), Some("14.42.34433"));
+        assert_eq!(env.get("UCRTVersion"), Some("10.0.22621.0"));
+        assert_eq!(env.get("WindowsSDKVersion"), Some("10.0.22621.0\\"));
+        assert_eq!(
+            env.get("VCToolsInstallDir").map(PathBuf::from),
+            Some(msvc.clone())
+        );
+        assert_eq!(env.get("VSCMD_ARG_TGT_ARCH"), Some("x64"));
+    }
+
+    #[test]
+    fn test_compute_cross() {
+        let (root, installation, toolset, win_sdk) = fixture();
+        let msvc = root.path().join("VS/VC/Tools/MSVC/14.42.34433");
+
+        let env = VsDevEnv::compute(
+            &installation,
+            &toolset,
+            &win_sdk,
+            HostTarget::new(Arch::X64, Arch::Arm64),
+        )
+        .expect("It should be possible to compute the cross compilation environment.");
+        assert_eq!(
+            env.paths("PATH")[..2],
+            [msvc.join("bin/Hostx64/arm64"), msvc.join("bin/Hostx64/x64")]
+        );
+        assert_eq!(
+            env.paths("LIB")[0],
+            msvc.join("lib/arm64"),
+            "The ATL libraries should only be added if they exist."
+        );
+
+        assert!(
+            VsDevEnv::compute(
+                &installation,
+                &toolset,
+                &win_sdk,
+                HostTarget::new(Arch::X64, Arch::X86)
+            )
+            .is_err(),
+            "The environment should not be computed without the x86 libraries."
+        );
+    }
+
+    #[test]
+    fn test_parse_set_output() {
+        let output = "=C:=C:\\Users\\builder\r\n\
+                      ALLUSERSPROFILE=C:\\ProgramData\r\n\
+                      ProgramFiles(x86)=C:\\Program Files (x86)\r\n\
+                      PROMPT=$P$G\r\n\
+                      EMPTY=\r\n\
+                      **********************************************************************\r\n\
+                      ** Visual Studio 2022 Developer Command Prompt v17.12.3\r\n";
+        assert_eq!(
+            VsDevEnv::parse_set_output(output),
+            [
+                ("ALLUSERSPROFILE", "C:\\ProgramData"),
+                ("ProgramFiles(x86)", "C:\\Program Files (x86)"),
+                ("PROMPT", "$P$G"),
+                ("EMPTY", ""),
+            ]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<(String, String)>>()
+        );
+    }
+
+    #[test]
+    fn test_from_set_output() {
+        let before = "INCLUDE=\r\n\
+                      Path=C:\\Windows\\system32;C:\\Windows\r\n\
+                      PROMPT=$P$G\r\n\
+                      TEMP=C:\\Temp\r\n";
+        let after = "INCLUDE=C:\\VS\\include;\r\n\
+                     LIB=C:\\VS\\lib\\x64;\r\n\
+                     Path=C:\\VS\\bin\\Hostx64\\x64;C:\\VS\\MSBuild;C:\\Windows\\system32;C:\\Windows\r\n\
+                     PROMPT=$P$G\r\n\
+                     VCToolsVersion=14.42.34433\r\n\
+                     VSCMD_ARG_TGT_ARCH=x64\r\n";
+        let env = VsDevEnv::from_set_output(before, after);
+        assert_eq!(
+            env.vars().keys().collect::<Vec<&String>>(),
+            [
+                "INCLUDE",
+                "LIB",
+                "PATH",
+                "VCToolsVersion",
+                "VSCMD_ARG_TGT_ARCH"
+            ]
+        );
+        assert_eq!(env.get("INCLUDE"), Some("C:\\VS\\include;"));
+        assert_eq!(
+            env.paths("PATH"),
+            [
+                PathBuf::from("C:\\VS\\bin\\Hostx64\\x64"),
+                PathBuf::from("C:\\VS\\MSBuild")
+            ]
+        );
+        assert_eq!(
+            env.get("TEMP"),
+            None,
+            "Removed variables should be ignored."
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let env = VsDevEnv {
+            vars: [("PATH", "C:\\VS\\bin"), ("VCToolsVersion", "14.42.34433")]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        let mut command = Command::new("cl.exe");
+        command.env("PATH", "C:\\Windows");
+        env.apply(&mut command);
+
+        let envs = command
+            .get_envs()
+            .map(|(k, v)| (k.to_os_string(), v.map(OsString::from)))
+            .collect::<BTreeMap<OsString, Option<OsString>>>();
+        assert_eq!(
+            envs.get(&OsString::from("PATH")),
+            Some(&Some(OsString::from("C:\\VS\\bin;C:\\Windows")))
+        );
+        assert_eq!(
+            envs.get(&OsString::from("VCToolsVersion")),
+            Some(&Some(OsString::from("14.42.34433")))
+        );
+    }
+}
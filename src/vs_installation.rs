@@ -1,32 +1,294 @@
 //! Module for code related to a full installation of VS or just
 //! the VS build tools.
-use crate::{versions::VsInstallationVersion, vs_where::VsWhere};
-use serde_json::Value;
-use std::{
-    io::{Error, ErrorKind},
-    path::{Path, PathBuf},
+use crate::{
+    error::{Error, Result},
+    glob,
+    installation_source::{self, InstallationSource},
+    selection::{
+        Candidate, CandidateStatus, InstallationCriteria, RejectionReason, SelectionReport,
+    },
+    versions::{self, VsInstallationVersion},
 };
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The version of an installation that fulfills the selection
+/// criteria or the reason why it was rejected.
+type Verdict<'a> = std::result::Result<VsInstallationVersion<'a>, RejectionReason>;
 
 /// Type containing information about the installation.
+///
+/// The information is deserialized from the instance data reported
+/// by vswhere. Instances of legacy products, i.e. VS 2015 and older,
+/// only contain the path, version and instance id.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VsInstallation {
+    #[serde(rename = "installationPath")]
     path: PathBuf,
+    installation_version: String,
+    instance_id: Option<String>,
+    installation_name: Option<String>,
+    display_name: Option<String>,
+    description: Option<String>,
+    product_id: Option<String>,
+    product_path: Option<PathBuf>,
+    channel_id: Option<String>,
+    is_prerelease: Option<bool>,
+    is_complete: Option<bool>,
+    is_launchable: Option<bool>,
+    install_date: Option<String>,
+    catalog: Option<VsCatalog>,
+    packages: Option<Vec<VsPackage>>,
+}
+
+/// Type containing the catalog information of an installation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VsCatalog {
+    build_version: Option<String>,
+    product_display_version: Option<String>,
+    product_line: Option<String>,
+    product_line_version: Option<String>,
+    product_name: Option<String>,
+    product_semantic_version: Option<String>,
+}
+
+/// Type containing information about a package, e.g. a workload or
+/// a component, that is part of an installation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VsPackage {
+    id: String,
+    version: Option<String>,
+    #[serde(rename = "type")]
+    package_type: Option<String>,
+}
+
+impl VsPackage {
+    /// The package id e.g. `Microsoft.VisualStudio.Component.VC.Tools.x86.x64`.
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// The version of the package.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// The type of the package e.g. `Workload` or `Component`.
+    pub fn package_type(&self) -> Option<&str> {
+        self.package_type.as_deref()
+    }
+}
+
+impl VsCatalog {
+    /// The build version e.g. `17.12.35506.116`.
+    pub fn build_version(&self) -> Option<&str> {
+        self.build_version.as_deref()
+    }
+
+    /// The version displayed to users e.g. `17.12.3`.
+    pub fn product_display_version(&self) -> Option<&str> {
+        self.product_display_version.as_deref()
+    }
+
+    /// The product line e.g. `Dev17`.
+    pub fn product_line(&self) -> Option<&str> {
+        self.product_line.as_deref()
+    }
+
+    /// The product line version e.g. `2022`.
+    pub fn product_line_version(&self) -> Option<&str> {
+        self.product_line_version.as_deref()
+    }
+
+    /// The product name e.g. `Visual Studio`.
+    pub fn product_name(&self) -> Option<&str> {
+        self.product_name.as_deref()
+    }
+
+    /// The semantic version e.g. `17.12.3+35527.113`.
+    pub fn product_semantic_version(&self) -> Option<&str> {
+        self.product_semantic_version.as_deref()
+    }
 }
 
 impl VsInstallation {
-    const ENV_KEY: &'static str = "VS_INSTALLATION_PATH";
+    pub(crate) const ENV_KEY: &'static str = "VS_INSTALLATION_PATH";
+    pub(crate) const INSTANCE_ID_ENV_KEY: &'static str = "VS_INSTANCE_ID";
 
     /// The path of the VS installation.
     pub fn path(&self) -> &Path {
         self.path.as_path()
     }
 
+    /// The installation version as reported by vswhere
+    /// e.g. `17.12.35506.116`.
+    pub fn installation_version(&self) -> Result<VsInstallationVersion<'_>> {
+        VsInstallationVersion::parse(self.installation_version.as_str())
+    }
+
+    /// The installation version as reported by vswhere.
+    pub(crate) fn installation_version_str(&self) -> &str {
+        self.installation_version.as_str()
+    }
+
+    /// The unique id of the installation instance.
+    pub fn instance_id(&self) -> Option<&str> {
+        self.instance_id.as_deref()
+    }
+
+    /// The installation name e.g. `VisualStudio/17.12.0+35506.116`.
+    pub fn installation_name(&self) -> Option<&str> {
+        self.installation_name.as_deref()
+    }
+
+    /// The display name of the installation
+    /// e.g. `Visual Studio Community 2022`.
+    pub fn display_name(&self) -> Option<&str> {
+        self.display_name.as_deref()
+    }
+
+    /// The description of the installation.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// The product id e.g. `Microsoft.VisualStudio.Product.Community`.
+    pub fn product_id(&self) -> Option<&str> {
+        self.product_id.as_deref()
+    }
+
+    /// The path of the product executable e.g. `devenv.exe`.
+    pub fn product_path(&self) -> Option<&Path> {
+        self.product_path.as_deref()
+    }
+
+    /// The channel id e.g. `VisualStudio.17.Release`.
+    pub fn channel_id(&self) -> Option<&str> {
+        self.channel_id.as_deref()
+    }
+
+    /// Whether or not the installation is a prerelease.
+    pub fn is_prerelease(&self) -> Option<bool> {
+        self.is_prerelease
+    }
+
+    /// Whether or not the installation is complete.
+    pub fn is_complete(&self) -> Option<bool> {
+        self.is_complete
+    }
+
+    /// Whether or not the installation can be launched.
+    pub fn is_launchable(&self) -> Option<bool> {
+        self.is_launchable
+    }
+
+    /// The date the installation was made e.g. `2023-08-26T14:05:02Z`.
+    pub fn install_date(&self) -> Option<&str> {
+        self.install_date.as_deref()
+    }
+
+    /// The catalog information of the installation.
+    pub fn catalog(&self) -> Option<&VsCatalog> {
+        self.catalog.as_ref()
+    }
+
+    /// The packages, i.e. workloads and components, of the installation.
+    pub fn packages(&self) -> &[VsPackage] {
+        self.packages.as_deref().unwrap_or_default()
+    }
+
+    /// Crate function for checking if the installation reports its
+    /// packages. vswhere only reports them with `-include packages`.
+    pub(crate) fn reports_packages(&self) -> bool {
+        self.packages.is_some()
+    }
+
+    /// Crate function for checking if the installation is a legacy
+    /// installation, i.e. VS 2015 or older, which vswhere reports
+    /// without a product id or catalog.
+    pub(crate) fn is_legacy(&self) -> bool {
+        self.product_id.is_none() && self.catalog.is_none()
+    }
+
+    /// Whether or not the installation contains the package with the id.
+    pub fn has_package(&self, package_id: &str) -> bool {
+        self.packages()
+            .iter()
+            .any(|p| p.id().eq_ignore_ascii_case(package_id))
+    }
+
+    /// Finds the files and directories inside the installation that
+    /// matches the pattern, e.g. `VC\Tools\MSVC\**\Hostx64\x64\cl.exe`.
+    ///
+    /// The pattern is interpreted like the `-find` argument of vswhere,
+    /// i.e. `*` and `?` match characters within a name and `**` matches
+    /// any number of directories, but the search is performed without
+    /// vswhere so it also works for installations specified using
+    /// `VS_INSTALLATION_PATH`. The matches are returned sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::VsInstallation;
+    ///
+    /// let installation = VsInstallation::find_in_range(None, None).expect("VS should exist");
+    /// let dumpbin = installation
+    ///     .find_files("VC\\Tools\\MSVC\\*\\bin\\Hostx64\\x64\\dumpbin.exe")
+    ///     .expect("The installation should be readable");
+    /// ```
+    pub fn find_files(&self, pattern: &str) -> Result<Vec<PathBuf>> {
+        trace!("Searching for {} in {}", pattern, self.path.display());
+        glob::find(self.path(), pattern).map_err(Error::from)
+    }
+
+    /// Lists all the VS installations reported by vswhere, in the
+    /// order they were reported, regardless of version. If the
+    /// `VS_WHERE_JSON` environment variable is set the installations
+    /// are instead read from the file, see [`crate::JsonFileSource`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::VsInstallation;
+    ///
+    /// for installation in VsInstallation::list_all().expect("vswhere should exist") {
+    ///     println!(
+    ///         "{} {}",
+    ///         installation.display_name().unwrap_or("Unknown"),
+    ///         installation.path().display()
+    ///     );
+    /// }
+    /// ```
+    pub fn list_all() -> Result<Vec<Self>> {
+        installation_source::default_source().and_then(|source| Self::list_in(source.as_ref()))
+    }
+
+    /// Lists all the VS installations of the source, in the order
+    /// they were reported, regardless of version. Instances that can
+    /// not be interpreted as installations are skipped.
+    pub fn list_in(source: &dyn InstallationSource) -> Result<Vec<Self>> {
+        source.instances().map(|instances| {
+            instances
+                .into_iter()
+                .filter_map(|instance| {
+                    Self::from_json(instance)
+                        .map_err(|e| warn!("Skipping the instance: {}", e))
+                        .ok()
+                })
+                .collect()
+        })
+    }
+
     /// Finds a VS installation with the highest installation version that is in a range
     /// between max (exclusive) and min(inclusive).
     /// # Examples
     ///
-    /// ```
+    /// ```no_run
     /// // Find the latest supported version for msbuild
-    /// use msbuild::installation::{VsInstallationVersion, VsInstallation};
+    /// use msbuild::{VsInstallationVersion, VsInstallation};
     ///
     /// let max = Some(VsInstallationVersion::parse("17.10.35013.160").unwrap());
     /// let min = Some(VsInstallationVersion::parse("17.0.0.0").unwrap());
@@ -36,137 +298,281 @@ impl VsInstallation {
     pub fn find_in_range(
         max: Option<VsInstallationVersion>,
         min: Option<VsInstallationVersion>,
-    ) -> std::io::Result<Self> {
-        VsWhere::find_vswhere()
-            .and_then(|vswhere| vswhere.run(None))
-            .and_then(|output| Self::parse_from_json(&output))
-            .and_then(|v: Value| {
-                Self::list_instances(&v)
-                    .and_then(|instances| Self::find_match(instances, max.as_ref(), min.as_ref()))
-            })
-            .map(|path| VsInstallation { path })
+    ) -> Result<Self> {
+        Self::find(&InstallationCriteria::new().version_range(max, min))
+    }
+
+    /// Finds the VS installation that best fulfills the criteria.
+    ///
+    /// The installations are reported by vswhere or, if the
+    /// `VS_WHERE_JSON` environment variable is set, read from the file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{InstallationCriteria, VsInstallation, VsProduct};
+    ///
+    /// let criteria = InstallationCriteria::new()
+    ///     .prefer_products([VsProduct::BuildTools, VsProduct::Enterprise]);
+    /// let vs_installation = VsInstallation::find(&criteria);
+    /// ```
+    pub fn find(criteria: &InstallationCriteria) -> Result<Self> {
+        installation_source::default_source()
+            .and_then(|source| Self::find_in(source.as_ref(), criteria))
+    }
+
+    /// Finds the VS installation of the source that best fulfills
+    /// the criteria.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{InstallationCriteria, VsInstallation, VsSetupInstances};
+    ///
+    /// // Find the installation without using vswhere.
+    /// let source = VsSetupInstances::find().expect("The instances directory should exist");
+    /// let vs_installation = VsInstallation::find_in(&source, &InstallationCriteria::new());
+    /// ```
+    pub fn find_in(
+        source: &dyn InstallationSource,
+        criteria: &InstallationCriteria,
+    ) -> Result<Self> {
+        Self::query_installations(source, criteria).and_then(|(installations, invalid)| {
+            Self::find_match(installations.as_slice(), invalid, criteria)
+        })
     }
 
-    // Internal function for finding the instances that matches the
-    // version range and, if specified, the path in the environment
-    // variable.
-    fn find_match(
-        instances_json: &[Value],
-        max: Option<&VsInstallationVersion>,
-        min: Option<&VsInstallationVersion>,
-    ) -> std::io::Result<PathBuf> {
+    /// Explains how the VS installation would be selected using the
+    /// criteria without selecting it, i.e. a dry-run of [`VsInstallation::find`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{InstallationCriteria, VsInstallation};
+    ///
+    /// let criteria = InstallationCriteria::new()
+    ///     .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64");
+    /// let report = VsInstallation::explain(&criteria).expect("vswhere should exist");
+    /// for candidate in report.candidates() {
+    ///     println!("{} {:?}", candidate.path().display(), candidate.status());
+    /// }
+    /// ```
+    pub fn explain(criteria: &InstallationCriteria) -> Result<SelectionReport> {
+        installation_source::default_source()
+            .and_then(|source| Self::explain_in(source.as_ref(), criteria))
+    }
+
+    /// Explains how the VS installation of the source would be selected
+    /// using the criteria, i.e. a dry-run of [`VsInstallation::find_in`].
+    pub fn explain_in(
+        source: &dyn InstallationSource,
+        criteria: &InstallationCriteria,
+    ) -> Result<SelectionReport> {
+        Self::query_installations(source, criteria).map(|(installations, invalid)| {
+            Self::select(installations.as_slice(), invalid, criteria).1
+        })
+    }
+
+    // Internal function that lists the installations of the source,
+    // with their packages if the criteria requires packages, together
+    // with candidates for the instances that could not be interpreted
+    // as installations.
+    fn query_installations(
+        source: &dyn InstallationSource,
+        criteria: &InstallationCriteria,
+    ) -> Result<(Vec<Self>, Vec<Candidate>)> {
+        if criteria.has_required_packages() {
+            source.instances_with_packages()
+        } else {
+            source.instances()
+        }
+        .map(Self::from_instances)
+    }
+
+    // Crate function that deserializes the json instances into
+    // installations and candidates for the instances that could
+    // not be interpreted as installations.
+    pub(crate) fn from_instances(instances: Vec<Value>) -> (Vec<Self>, Vec<Candidate>) {
+        let mut installations = Vec::new();
+        let mut invalid = Vec::new();
+        instances.into_iter().for_each(|i| {
+            let path = i
+                .get("installationPath")
+                .and_then(Value::as_str)
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            match serde_json::from_value::<Self>(i) {
+                Ok(installation) => installations.push(installation),
+                Err(e) => {
+                    warn!(
+                        "Failed to parse the instance data of {}: {}",
+                        path.display(),
+                        e
+                    );
+                    invalid.push(Candidate::invalid(path, e.to_string()))
+                }
+            }
+        });
+        (installations, invalid)
+    }
+
+    // Crate function for deserializing an installation from the
+    // json data of a vswhere instance.
+    pub(crate) fn from_json(json_value: Value) -> Result<Self> {
+        serde_json::from_value(json_value).map_err(|e| Error::VsWhereOutputInvalid {
+            reason: "Failed to parse installation instance.".to_string(),
+            source: Some(e),
+        })
+    }
+
+    // Crate function for finding the instance that matches the
+    // criteria.
+    pub(crate) fn find_match(
+        installations: &[VsInstallation],
+        invalid: Vec<Candidate>,
+        criteria: &InstallationCriteria,
+    ) -> Result<VsInstallation> {
+        let (selected, report) = Self::select(installations, invalid, criteria);
+        selected.ok_or_else(|| Error::NoMatchingInstallation(Box::new(report)))
+    }
+
+    // Internal function for selecting the instance that matches the
+    // criteria and, if specified, the instance id and path in the
+    // environment variables. The invalid candidates are only added
+    // to the report.
+    fn select(
+        installations: &[VsInstallation],
+        invalid: Vec<Candidate>,
+        criteria: &InstallationCriteria,
+    ) -> (Option<VsInstallation>, SelectionReport) {
         let env_installation_path: Option<PathBuf> =
             std::env::var(Self::ENV_KEY).ok().map(|v| PathBuf::from(&v));
+        trace!("Consulted {}: {:?}", Self::ENV_KEY, env_installation_path);
+        let env_instance_id = std::env::var(Self::INSTANCE_ID_ENV_KEY);
+        trace!(
+            "Consulted {}: {:?}",
+            Self::INSTANCE_ID_ENV_KEY,
+            env_instance_id.as_ref().ok()
+        );
+        let criteria = match env_instance_id {
+            Ok(instance_id) => criteria.clone().instance_id(instance_id),
+            Err(_) => criteria.clone(),
+        };
 
-        // Parse the instance json data and filter result based on version.
-        let validated_instances = Self::validate_instances_json(instances_json, max, min);
+        // Filter the installations based on the criteria.
+        let mut verdicts = Self::validate_instances(installations, &criteria);
 
         if let Some(specified_installation_path) = env_installation_path {
-            // Finds the specified installation path among the parsed
-            // and validated instances.
-            validated_instances
+            // Only keep the installations, among the validated instances, with
+            // the longest path that contains the specified installation path.
+            let contains_specified_path =
+                |i: &VsInstallation| specified_installation_path.starts_with(i.path());
+            let deepest = verdicts
                 .iter()
-                .filter_map(|(_, p)| {
-                    if specified_installation_path.starts_with(p) {
-                        Some(p.to_path_buf())
-                    } else {
-                        None
-                    }
+                .filter(|(i, verdict)| verdict.is_ok() && contains_specified_path(i))
+                .map(|(i, _)| i.path().components().count())
+                .max();
+            verdicts
+                .iter_mut()
+                .filter(|(i, verdict)| {
+                    verdict.is_ok()
+                        && (!contains_specified_path(i)
+                            || Some(i.path().components().count()) != deepest)
                 })
-                .next()
-                .ok_or(Error::new(
-                    ErrorKind::NotFound,
-                    "No instance found that matched requirements.",
-                ))
-        } else {
-            // Select the latest version.
-            validated_instances
-                .iter()
-                .max_by_key(|(v, _)| v)
-                .map(|(_, p)| p.to_path_buf())
-                .ok_or(Error::new(
-                    ErrorKind::NotFound,
-                    "No instance found that matched requirements.",
-                ))
+                .for_each(|(_, verdict)| *verdict = Err(RejectionReason::NotSpecifiedPath));
         }
+
+        // Select the preferred installation.
+        let selected = verdicts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (i, verdict))| verdict.as_ref().ok().map(|v| (index, (v, *i))))
+            .min_by(|(_, a), (_, b)| criteria.compare(*a, *b))
+            .map(|(index, _)| index);
+
+        let candidates = verdicts
+            .iter()
+            .enumerate()
+            .map(|(index, (i, verdict))| {
+                let status = match verdict {
+                    Err(reason) => CandidateStatus::Rejected(reason.clone()),
+                    Ok(_) if Some(index) == selected => CandidateStatus::Selected,
+                    Ok(_) => CandidateStatus::Accepted,
+                };
+                Candidate::new(i, status)
+            })
+            .chain(invalid)
+            .collect();
+        let report = SelectionReport::new(
+            versions::describe_range(criteria.max(), criteria.min()),
+            candidates,
+        );
+        debug!("{}", report);
+        (selected.map(|index| verdicts[index].0.clone()), report)
     }
 
-    /// Internal function that extracts a collection of parsed
-    /// installation instances with a version within the given
-    /// interval.
-    fn validate_instances_json<'a>(
-        instances_json: &'a [Value],
-        max: Option<&'a VsInstallationVersion>,
-        min: Option<&'a VsInstallationVersion>,
-    ) -> Vec<(VsInstallationVersion<'a>, &'a Path)> {
-        instances_json
+    /// Internal function that checks each of the installations
+    /// against the criteria. Installations that fulfill the criteria
+    /// are paired with their version and the others with the reason
+    /// why they were rejected.
+    fn validate_instances<'a>(
+        installations: &'a [VsInstallation],
+        criteria: &InstallationCriteria,
+    ) -> Vec<(&'a VsInstallation, Verdict<'a>)> {
+        installations
             .iter()
-            .filter_map(|i| {
-                VsInstallation::parse_installation_version(i)
+            .map(|i| {
+                let verdict = i
+                    .installation_version()
+                    .map_err(|e| RejectionReason::InvalidVersion(e.to_string()))
                     .and_then(|installation_version| {
-                        if installation_version.is_in_range(max, min) {
-                            VsInstallation::parse_installation_path(i).map(|installation_path| {
-                                Some((installation_version, installation_path))
-                            })
+                        if installation_version.is_in_range(criteria.max(), criteria.min()) {
+                            Ok(installation_version)
                         } else {
-                            // Maybe log(trace) that an instance was found that was not in the range.
-                            Ok(None)
+                            Err(RejectionReason::VersionOutOfRange(
+                                installation_version.to_string(),
+                            ))
                         }
                     })
-                    .unwrap_or_else(|e| {
-                        print!("Encounted an error during parsing of instance data: {}", e);
-                        None
+                    .and_then(|installation_version| {
+                        if criteria.accepts_product(i.product_id()) {
+                            Ok(installation_version)
+                        } else {
+                            Err(RejectionReason::ProductNotAccepted(i.product_id.clone()))
+                        }
+                    })
+                    .and_then(|installation_version| {
+                        if criteria.accepts_instance(i.instance_id()) {
+                            Ok(installation_version)
+                        } else {
+                            Err(RejectionReason::NotPinnedInstance)
+                        }
+                    })
+                    .and_then(|installation_version| {
+                        criteria.check_policy(i).map(|_| installation_version)
                     })
+                    .and_then(|installation_version| {
+                        criteria.check_packages(i).map(|_| installation_version)
+                    });
+                (i, verdict)
             })
             .collect()
     }
 
-    // Internal function for parsing a string as json object.
-    fn parse_from_json(value: &str) -> std::io::Result<Value> {
-        serde_json::from_str(value).map_err(|e| {
-            Error::new(
-                ErrorKind::InvalidData,
-                format!("Failed to parse command output as json ({})", e),
-            )
-        })
-    }
-
-    // Internal function for listing the instances inthe json value.
-    fn list_instances(v: &Value) -> std::io::Result<&Vec<Value>> {
-        v.as_array().ok_or_else(|| {
-            Error::new(
-                ErrorKind::InvalidData,
-                "json data did not contain any installation instances.",
-            )
-        })
-    }
-
-    /// Function for parsing the installation path from
-    /// the return value of `vs_where`.
-    fn parse_installation_path(json_value: &Value) -> std::io::Result<&Path> {
-        json_value
-            .get("installationPath")
-            .and_then(|path_json_value: &Value| path_json_value.as_str())
-            .ok_or(Error::new(
-                ErrorKind::InvalidData,
-                "Failed to retrieve `installationPath`.",
-            ))
-            .map(Path::new)
-    }
-
-    /// Function for parsing the installation version from
-    /// the return value of `vs_where`.
-    fn parse_installation_version(
-        json_value: &Value,
-    ) -> std::io::Result<VsInstallationVersion<'_>> {
-        json_value
-            .get("installationVersion")
-            .and_then(|version_json_value: &Value| version_json_value.as_str())
-            .and_then(|version_str: &str| VsInstallationVersion::parse(version_str).ok())
-            .ok_or(Error::new(
-                ErrorKind::InvalidData,
-                "Failed to retrieve `installationVersion`.",
-            ))
+    // Crate function for parsing the vswhere output as a
+    // list of json instances.
+    pub(crate) fn parse_from_json(value: &str) -> Result<Vec<Value>> {
+        serde_json::from_str::<Value>(value)
+            .map_err(|e| Error::VsWhereOutputInvalid {
+                reason: "Failed to parse command output as json.".to_string(),
+                source: Some(e),
+            })
+            .and_then(|v| match v {
+                Value::Array(instances) => Ok(instances),
+                _ => Err(Error::vswhere_output_invalid(
+                    "json data did not contain any installation instances.",
+                )),
+            })
     }
 }
 
@@ -176,9 +582,16 @@ impl VsInstallation {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::selection::VsProduct;
+
+    // Helper for deserializing a json array of instances.
+    fn installations(json_value: Value) -> Vec<VsInstallation> {
+        serde_json::from_value(json_value)
+            .expect("It should be possible to deserialize the instances.")
+    }
 
     #[test]
-    fn test_parse_installation_version() {
+    fn test_from_json_installation_version() {
         let version_str = "2.3.1.34";
         let json_value = serde_json::json!({
             "instanceId": "VisualStudio.14.0",
@@ -187,15 +600,20 @@ mod test {
         });
         let expected = VsInstallationVersion::parse(version_str)
             .expect("It should be possible to parse the `version_str` as Version object.");
-        let actual = VsInstallation::parse_installation_version(&json_value).expect(
+        let installation = VsInstallation::from_json(json_value)
+            .expect("The function should be able to deserialize a legacy instance.");
+        let actual = installation.installation_version().expect(
             "The function should be to extract an installation version from the json_value.",
         );
         assert_eq!(expected, actual);
+        assert_eq!(installation.instance_id(), Some("VisualStudio.14.0"));
+        assert_eq!(installation.product_id(), None);
+        assert!(installation.catalog().is_none());
     }
 
     #[test]
-    fn test_parse_installation_path() {
-        let expected = Path::new("C:\\Program Files (x86)\\Microsoft Visual Studio 14.0\\");
+    fn test_from_json_metadata() {
+        let expected = Path::new("C:\\Program Files\\Microsoft Visual Studio\\2022\\Community");
         let json_value = serde_json::json!({
             "instanceId": "019109ba",
             "installDate": "2023-08-26T14:05:02Z",
@@ -204,10 +622,89 @@ mod test {
             "installationVersion": "17.12.35506.116",
             "productId": "Microsoft.VisualStudio.Product.Community",
             "productPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community\\Common7\\IDE\\devenv.exe",
+            "state": 4294967295u32,
+            "isComplete": true,
+            "isLaunchable": true,
+            "isPrerelease": false,
+            "isRebootRequired": false,
+            "displayName": "Visual Studio Community 2022",
+            "description": "Powerful IDE, free for students, open-source contributors, and individuals",
+            "channelId": "VisualStudio.17.Release",
+            "channelUri": "https://aka.ms/vs/17/release/channel",
+            "enginePath": "C:\\Program Files (x86)\\Microsoft Visual Studio\\Installer\\resources\\app\\ServiceHub\\Services\\Microsoft.VisualStudio.Setup.Service",
+            "catalog": {
+                "buildBranch": "d17.12",
+                "buildVersion": "17.12.35506.116",
+                "id": "VisualStudio/17.12.0+35506.116",
+                "productDisplayVersion": "17.12.0",
+                "productLine": "Dev17",
+                "productLineVersion": "2022",
+                "productName": "Visual Studio",
+                "productSemanticVersion": "17.12.0+35506.116"
+            },
+            "properties": {
+                "nickname": ""
+            }
         });
-        let actual = VsInstallation::parse_installation_path(&json_value)
-            .expect("The function should be to extract an installation path from the json_value.");
-        assert_eq!(expected, actual);
+        let actual = VsInstallation::from_json(json_value)
+            .expect("The function should be to deserialize the json_value.");
+        assert_eq!(expected, actual.path());
+        assert_eq!(actual.instance_id(), Some("019109ba"));
+        assert_eq!(actual.install_date(), Some("2023-08-26T14:05:02Z"));
+        assert_eq!(
+            actual.installation_name(),
+            Some("VisualStudio/17.12.0+35506.116")
+        );
+        assert_eq!(
+            actual.product_id(),
+            Some("Microsoft.VisualStudio.Product.Community")
+        );
+        assert_eq!(
+            actual.product_path(),
+            Some(Path::new(
+                "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community\\Common7\\IDE\\devenv.exe"
+            ))
+        );
+        assert_eq!(actual.display_name(), Some("Visual Studio Community 2022"));
+        assert_eq!(actual.channel_id(), Some("VisualStudio.17.Release"));
+        assert_eq!(actual.is_complete(), Some(true));
+        assert_eq!(actual.is_launchable(), Some(true));
+        assert_eq!(actual.is_prerelease(), Some(false));
+        let catalog = actual
+            .catalog()
+            .expect("The catalog should have been deserialized.");
+        assert_eq!(catalog.product_line_version(), Some("2022"));
+        assert_eq!(catalog.product_line(), Some("Dev17"));
+        assert_eq!(catalog.product_display_version(), Some("17.12.0"));
+    }
+
+    #[test]
+    fn test_from_json_missing_path() {
+        let json_value = serde_json::json!({
+            "installationVersion": "17.12.35506.116",
+        });
+        assert!(matches!(
+            VsInstallation::from_json(json_value),
+            Err(Error::VsWhereOutputInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_from_json() {
+        assert!(matches!(
+            VsInstallation::parse_from_json("{"),
+            Err(Error::VsWhereOutputInvalid {
+                source: Some(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            VsInstallation::parse_from_json("{}"),
+            Err(Error::VsWhereOutputInvalid { source: None, .. })
+        ));
+        let actual = VsInstallation::parse_from_json("[{}, {}]")
+            .expect("It should be possible to parse a json array.");
+        assert_eq!(actual.len(), 2);
     }
 
     #[test]
@@ -227,9 +724,7 @@ mod test {
             },
         ]);
 
-        let values: &Vec<Value> = json_value
-            .as_array()
-            .expect("It should be possible to parse the json as an array of objects.");
+        let values = installations(json_value);
 
         // Sanity check.
         assert_eq!(
@@ -247,8 +742,11 @@ mod test {
             VsInstallationVersion::parse("18.0")
                 .expect("It should be possible to parse the 18.0 as a version."),
         );
-        let validated_instances =
-            VsInstallation::validate_instances_json(values.as_slice(), max.as_ref(), min.as_ref());
+        let criteria = InstallationCriteria::new().version_range(max, min);
+        let validated_instances = VsInstallation::validate_instances(values.as_slice(), &criteria)
+            .into_iter()
+            .filter_map(|(i, verdict)| verdict.ok().map(|v| (v, i)))
+            .collect::<Vec<(VsInstallationVersion, &VsInstallation)>>();
         let expected_version = VsInstallationVersion::parse("17.12.35506.116")
             .expect("It should be possible to parse avlid version.");
         let expected_path =
@@ -258,13 +756,14 @@ mod test {
             1,
             "There should only be 1 element found."
         );
-        let (actual_version, actual_path) = validated_instances.first().unwrap();
+        let (actual_version, actual_installation) = validated_instances.first().unwrap();
         assert_eq!(
             expected_version, *actual_version,
             "The returned version was not the expected one",
         );
         assert_eq!(
-            expected_path, *actual_path,
+            expected_path,
+            actual_installation.path(),
             "The returned path was not the expected one."
         );
     }
@@ -286,9 +785,7 @@ mod test {
             },
         ]);
 
-        let values: &Vec<Value> = json_value
-            .as_array()
-            .expect("It should be possible to parse the json as an array of objects.");
+        let values = installations(json_value);
 
         // Sanity check.
         assert_eq!(
@@ -313,12 +810,319 @@ mod test {
         // is the one with the latest version.
         let expected = PathBuf::from("C:\\Program Files\\Microsoft Visual Studio\\2022\\Community");
 
-        let actual = VsInstallation::find_match(values, max.as_ref(), min.as_ref())
+        let criteria = InstallationCriteria::new().version_range(max, min);
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
             .expect("The function is expected to return a valid result.");
 
         assert_eq!(
-            expected, actual,
+            expected,
+            actual.path(),
             "The resulting path does not match the expected one."
         );
     }
+
+    #[test]
+    fn test_msbuild_find_match_no_match() {
+        let json_value = serde_json::json!([
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+                "installationVersion": "17.12.35506.116",
+            },
+        ]);
+        let values = installations(json_value);
+        let min = Some(
+            VsInstallationVersion::parse("18.0")
+                .expect("It should be possible to parse the 18.0 as a version."),
+        );
+
+        let criteria = InstallationCriteria::new().version_range(None, min);
+        let error = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
+            .expect_err("No instance should match the version range.");
+        let report = error
+            .selection_report()
+            .expect("The error should contain a selection report.");
+        assert_eq!(report.requested_range(), ">=18.0.0");
+        assert_eq!(report.candidates().len(), 1);
+        let candidate = &report.candidates()[0];
+        assert_eq!(
+            candidate.path(),
+            Path::new("C:\\Program Files\\Microsoft Visual Studio\\2022\\Community")
+        );
+        assert_eq!(
+            *candidate.status(),
+            CandidateStatus::Rejected(RejectionReason::VersionOutOfRange(
+                "17.12.35506.116".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_msbuild_find_match_products() {
+        let json_value = serde_json::json!([
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+                "installationVersion": "17.12.35506.116",
+                "productId": "Microsoft.VisualStudio.Product.Community",
+            },
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Enterprise",
+                "installationVersion": "17.8.34322.80",
+                "productId": "Microsoft.VisualStudio.Product.Enterprise",
+            },
+            {
+                "installationPath": "C:\\Program Files (x86)\\Microsoft Visual Studio\\2022\\BuildTools",
+                "installationVersion": "17.10.35013.160",
+                "productId": "Microsoft.VisualStudio.Product.BuildTools",
+            },
+        ]);
+        let values = installations(json_value);
+
+        let criteria = InstallationCriteria::new()
+            .prefer_products([VsProduct::BuildTools, VsProduct::Enterprise]);
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
+            .expect("It should be possible to find the preferred product.");
+        assert_eq!(
+            actual.product_id(),
+            Some("Microsoft.VisualStudio.Product.BuildTools")
+        );
+
+        let criteria = InstallationCriteria::new()
+            .exclude_product(VsProduct::BuildTools)
+            .prefer_products([VsProduct::BuildTools, VsProduct::Enterprise]);
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
+            .expect("It should be possible to find the next preferred product.");
+        assert_eq!(
+            actual.product_id(),
+            Some("Microsoft.VisualStudio.Product.Enterprise")
+        );
+
+        let criteria = InstallationCriteria::new().exclude_product(VsProduct::BuildTools);
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria).expect(
+            "It should be possible to find the latest version when no preference is given.",
+        );
+        assert_eq!(
+            actual.product_id(),
+            Some("Microsoft.VisualStudio.Product.Community")
+        );
+
+        let criteria = InstallationCriteria::new().include_product(VsProduct::Professional);
+        assert!(matches!(
+            VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria),
+            Err(Error::NoMatchingInstallation(_))
+        ));
+    }
+
+    #[test]
+    fn test_msbuild_find_match_requirements() {
+        let json_value = serde_json::json!([
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+                "installationVersion": "17.12.35506.116",
+                "packages": [
+                    {
+                        "id": "Microsoft.VisualStudio.Workload.ManagedDesktop",
+                        "type": "Workload"
+                    }
+                ]
+            },
+            {
+                "installationPath": "C:\\Program Files (x86)\\Microsoft Visual Studio\\2022\\BuildTools",
+                "installationVersion": "17.10.35013.160",
+                "packages": [
+                    {
+                        "id": "Microsoft.VisualStudio.Component.VC.Tools.x86.x64",
+                        "type": "Component"
+                    }
+                ]
+            },
+        ]);
+        let values = installations(json_value);
+
+        let criteria = InstallationCriteria::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64");
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
+            .expect("It should be possible to find the installation with the component.");
+        assert_eq!(
+            actual.path(),
+            Path::new("C:\\Program Files (x86)\\Microsoft Visual Studio\\2022\\BuildTools")
+        );
+
+        let criteria =
+            InstallationCriteria::new().require("Microsoft.VisualStudio.Component.VC.Tools.ARM64");
+        match VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria) {
+            Err(Error::NoMatchingInstallation(report)) => {
+                assert_eq!(report.candidates().len(), 2);
+                assert!(report.candidates().iter().all(|c| *c.status()
+                    == CandidateStatus::Rejected(RejectionReason::MissingPackages(vec![
+                        "Microsoft.VisualStudio.Component.VC.Tools.ARM64".to_string()
+                    ]))));
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_msbuild_find_match_policy() {
+        let json_value = serde_json::json!([
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Preview",
+                "installationVersion": "17.13.35507.96",
+                "isPrerelease": true,
+                "isComplete": true,
+                "isLaunchable": true,
+            },
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Enterprise",
+                "installationVersion": "17.12.35506.116",
+                "isPrerelease": false,
+                "isComplete": false,
+                "isLaunchable": true,
+            },
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+                "installationVersion": "17.10.35013.160",
+                "isPrerelease": false,
+                "isComplete": true,
+                "isLaunchable": true,
+            },
+        ]);
+        let values = installations(json_value);
+
+        let actual =
+            VsInstallation::find_match(values.as_slice(), Vec::new(), &InstallationCriteria::new())
+                .expect("It should be possible to find a stable and complete installation.");
+        assert_eq!(
+            actual.path(),
+            Path::new("C:\\Program Files\\Microsoft Visual Studio\\2022\\Community")
+        );
+
+        let criteria = InstallationCriteria::new().allow_prerelease(true);
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
+            .expect("It should be possible to find the prerelease installation.");
+        assert_eq!(actual.is_prerelease(), Some(true));
+    }
+
+    #[test]
+    fn test_msbuild_find_match_tie_break() {
+        let json_value = serde_json::json!([
+            {
+                "instanceId": "b2c3d4e5",
+                "installationPath": "D:\\VS\\2022\\BuildTools",
+                "installationVersion": "17.12.35506.116",
+            },
+            {
+                "installationPath": "E:\\VS\\2022\\BuildTools",
+                "installationVersion": "17.12.35506.116",
+            },
+            {
+                "instanceId": "a1b2c3d4",
+                "installationPath": "C:\\VS\\2022\\BuildTools",
+                "installationVersion": "17.12.35506.116",
+            },
+        ]);
+        let mut values = installations(json_value);
+
+        // The same installation should be selected regardless of the order.
+        for _ in 0..values.len() {
+            let actual = VsInstallation::find_match(
+                values.as_slice(),
+                Vec::new(),
+                &InstallationCriteria::new(),
+            )
+            .expect("It should be possible to find an installation.");
+            assert_eq!(actual.instance_id(), Some("a1b2c3d4"));
+            values.rotate_left(1);
+        }
+
+        let criteria = InstallationCriteria::new().instance_id("b2c3d4e5");
+        let actual = VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria)
+            .expect("It should be possible to find the pinned installation.");
+        assert_eq!(actual.path(), Path::new("D:\\VS\\2022\\BuildTools"));
+
+        let criteria = InstallationCriteria::new().instance_id("c3d4e5f6");
+        match VsInstallation::find_match(values.as_slice(), Vec::new(), &criteria) {
+            Err(Error::NoMatchingInstallation(report)) => {
+                assert!(report.candidates().iter().all(|c| *c.status()
+                    == CandidateStatus::Rejected(RejectionReason::NotPinnedInstance)))
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_select_report() {
+        let json_value = serde_json::json!([
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community",
+                "installationVersion": "17.12.35506.116",
+            },
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2022\\Enterprise",
+                "installationVersion": "17.8.34322.80",
+            },
+            {
+                "installationPath": "C:\\Program Files\\Microsoft Visual Studio\\2019\\Community",
+                "installationVersion": "16.11.35431.28",
+            },
+        ]);
+        let values = installations(json_value);
+        let invalid = vec![Candidate::invalid(
+            PathBuf::new(),
+            "missing field `installationPath`".to_string(),
+        )];
+        let criteria = InstallationCriteria::new().version_range(
+            None,
+            Some(
+                VsInstallationVersion::parse("17.0")
+                    .expect("It should be possible to parse the 17.0 as a version."),
+            ),
+        );
+        let (selected, report) = VsInstallation::select(values.as_slice(), invalid, &criteria);
+        assert_eq!(
+            selected.map(|i| i.path),
+            Some(PathBuf::from(
+                "C:\\Program Files\\Microsoft Visual Studio\\2022\\Community"
+            ))
+        );
+        let statuses = report
+            .candidates()
+            .iter()
+            .map(|c| c.status().clone())
+            .collect::<Vec<CandidateStatus>>();
+        assert_eq!(
+            statuses,
+            [
+                CandidateStatus::Selected,
+                CandidateStatus::Accepted,
+                CandidateStatus::Rejected(RejectionReason::VersionOutOfRange(
+                    "16.11.35431.28".to_string()
+                )),
+                CandidateStatus::Rejected(RejectionReason::InvalidInstance(
+                    "missing field `installationPath`".to_string()
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_files() {
+        let root =
+            tempfile::tempdir().expect("It should be possible to create a temporary directory.");
+        let tools_dir = root
+            .path()
+            .join("VC/Tools/MSVC/14.42.34433/bin/Hostx64/x64");
+        std::fs::create_dir_all(&tools_dir)
+            .expect("It should be possible to create the directories.");
+        std::fs::write(tools_dir.join("dumpbin.exe"), "")
+            .expect("It should be possible to create the file.");
+        let installation: VsInstallation = serde_json::from_value(serde_json::json!({
+            "installationPath": root.path(),
+            "installationVersion": "17.12.35506.116",
+        }))
+        .expect("It should be possible to deserialize the installation.");
+        let actual = installation
+            .find_files("VC\\Tools\\MSVC\\*\\bin\\Hostx64\\x64\\dumpbin.exe")
+            .expect("It should be possible to find the files.");
+        assert_eq!(actual, [tools_dir.join("dumpbin.exe")]);
+    }
 }
@@ -1,14 +1,19 @@
 //! Module for llvm parts of a VS installation.
-use crate::{vs_paths::sub_directory, VsInstallation};
+use crate::{
+    arch::Arch,
+    error::{Error, Result},
+    vs_paths::sub_directory,
+    VsInstallation,
+};
 use std::{
     convert::TryFrom,
-    io::Error,
     path::{Path, PathBuf},
 };
 
 /// Type holding the paths associated with LLVM in the
 /// Visual compiler tools.
 pub struct VsLlvm {
+    root: PathBuf,
     bin: PathBuf,
     lib: PathBuf,
     bin_x64: PathBuf,
@@ -16,37 +21,136 @@ pub struct VsLlvm {
 }
 
 impl VsLlvm {
-    const BIN: &'static str = "VC/Tools/Llvm/bin";
-    const LIB: &'static str = "VC/Tools/Llvm/lib";
-    const BIN_X64: &'static str = "VC/Tools/Llvm/x64/bin";
-    const LIB_X64: &'static str = "VC/Tools/Llvm/x64/lib";
+    const ROOT: &'static str = "VC/Tools/Llvm";
 
+    /// The directory containing the x86 LLVM tools. The directory
+    /// does not necessarily exist, e.g. in an arm64 only installation.
+    #[deprecated(note = "use `VsLlvm::bin_for(Arch::X86)` which checks that the directory exists")]
     pub fn bin(&self) -> &Path {
-        self.bin.as_ref()
+        self.bin.as_path()
     }
 
+    /// The directory containing the x86 LLVM libraries. The directory
+    /// does not necessarily exist, e.g. in an arm64 only installation.
+    #[deprecated(note = "use `VsLlvm::lib_for(Arch::X86)` which checks that the directory exists")]
     pub fn lib(&self) -> &Path {
-        self.lib.as_ref()
+        self.lib.as_path()
     }
 
+    /// The directory containing the x64 LLVM tools. The directory
+    /// does not necessarily exist, e.g. in an arm64 only installation.
+    #[deprecated(note = "use `VsLlvm::bin_for(Arch::X64)` which checks that the directory exists")]
     pub fn bin_x64(&self) -> &Path {
-        self.bin_x64.as_ref()
+        self.bin_x64.as_path()
     }
 
+    /// The directory containing the x64 LLVM libraries. The directory
+    /// does not necessarily exist, e.g. in an arm64 only installation.
+    #[deprecated(note = "use `VsLlvm::lib_for(Arch::X64)` which checks that the directory exists")]
     pub fn lib_x64(&self) -> &Path {
-        self.lib_x64.as_ref()
+        self.lib_x64.as_path()
+    }
+
+    /// The directory containing the LLVM tools, e.g. `clang-cl.exe`,
+    /// that run on the host. The x86 tools are located directly in
+    /// `VC/Tools/Llvm/bin` and the tools for the other hosts in
+    /// `VC/Tools/Llvm/<host>/bin`.
+    pub fn bin_for(&self, host: Arch) -> Result<PathBuf> {
+        sub_directory(self.root.as_path(), Self::host_dir(host, "bin").as_str())
+    }
+
+    /// The directory containing the LLVM libraries for the host,
+    /// laid out in the same way as [`VsLlvm::bin_for`].
+    pub fn lib_for(&self, host: Arch) -> Result<PathBuf> {
+        sub_directory(self.root.as_path(), Self::host_dir(host, "lib").as_str())
+    }
+
+    // Internal function that returns the directory for the host
+    // relative to the LLVM root directory.
+    fn host_dir(host: Arch, dir: &str) -> String {
+        match host {
+            Arch::X86 => dir.to_string(),
+            host => format!("{}/{}", host.dir_name(), dir),
+        }
     }
 }
 
 impl TryFrom<&VsInstallation> for VsLlvm {
     type Error = Error;
 
-    fn try_from(vs_installation: &VsInstallation) -> std::io::Result<VsLlvm> {
-        Ok(VsLlvm {
-            bin: sub_directory(vs_installation.path(), Self::BIN)?,
-            lib: sub_directory(vs_installation.path(), Self::LIB)?,
-            bin_x64: sub_directory(vs_installation.path(), Self::BIN_X64)?,
-            lib_x64: sub_directory(vs_installation.path(), Self::LIB_X64)?,
+    fn try_from(vs_installation: &VsInstallation) -> Result<VsLlvm> {
+        sub_directory(vs_installation.path(), Self::ROOT).map(|root| VsLlvm {
+            bin: root.join(Self::host_dir(Arch::X86, "bin")),
+            lib: root.join(Self::host_dir(Arch::X86, "lib")),
+            bin_x64: root.join(Self::host_dir(Arch::X64, "bin")),
+            lib_x64: root.join(Self::host_dir(Arch::X64, "lib")),
+            root,
         })
     }
 }
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_host_dir() {
+        assert_eq!(VsLlvm::host_dir(Arch::X86, "bin"), "bin");
+        assert_eq!(VsLlvm::host_dir(Arch::X64, "bin"), "x64/bin");
+        assert_eq!(VsLlvm::host_dir(Arch::Arm64, "lib"), "arm64/lib");
+        assert_eq!(VsLlvm::host_dir(Arch::Arm64ec, "lib"), "arm64/lib");
+    }
+
+    #[test]
+    fn test_arm64_only() {
+        let root = tempdir().expect("It should be possible to create a temporary directory.");
+        ["arm64/bin", "arm64/lib"].iter().for_each(|dir| {
+            std::fs::create_dir_all(root.path().join(VsLlvm::ROOT).join(dir))
+                .expect("It should be possible to create the LLVM directories.")
+        });
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": root.path(),
+            "installationVersion": "17.12.35527.113",
+        }))
+        .expect("It should be possible to create the installation.");
+
+        let llvm = VsLlvm::try_from(&installation)
+            .expect("It should be possible to find LLVM without the x64 tools.");
+        assert_eq!(
+            llvm.bin_for(Arch::Arm64)
+                .expect("It should be possible to find the arm64 tools."),
+            root.path().join("VC/Tools/Llvm/arm64/bin")
+        );
+        assert_eq!(
+            llvm.lib_for(Arch::Arm64ec)
+                .expect("It should be possible to find the arm64 libraries."),
+            root.path().join("VC/Tools/Llvm/arm64/lib")
+        );
+        assert!(matches!(
+            llvm.bin_for(Arch::X64),
+            Err(Error::MissingComponentDirectory { .. })
+        ));
+        assert!(llvm.lib_for(Arch::X86).is_err());
+
+        #[allow(deprecated)]
+        {
+            assert_eq!(llvm.bin(), root.path().join("VC/Tools/Llvm/bin"));
+            assert_eq!(llvm.lib(), root.path().join("VC/Tools/Llvm/lib"));
+            assert_eq!(llvm.bin_x64(), root.path().join("VC/Tools/Llvm/x64/bin"));
+            assert_eq!(llvm.lib_x64(), root.path().join("VC/Tools/Llvm/x64/lib"));
+            assert!(!llvm.bin_x64().exists());
+        }
+
+        let empty = tempdir().expect("It should be possible to create a temporary directory.");
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": empty.path(),
+            "installationVersion": "17.12.35527.113",
+        }))
+        .expect("It should be possible to create the installation.");
+        assert!(VsLlvm::try_from(&installation).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+//! Module for the MSVC toolset parts of a VS installation.
+pub use crate::versions::MsvcToolsetVersion;
+use crate::{
+    arch::{Arch, HostTarget},
+    error::{Error, Result},
+    versions,
+    vs_paths::sub_directory,
+    VsInstallation,
+};
+use std::{
+    convert::TryFrom,
+    path::{Path, PathBuf},
+};
+
+/// Type holding the paths of a MSVC toolset, i.e. the compiler,
+/// linker, headers and libraries in `VC/Tools/MSVC/<version>`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{Arch, HostTarget, InstallationCriteria, VsInstallation, VsMsvcToolset};
+/// use std::convert::TryFrom;
+///
+/// let installation = VsInstallation::find(&InstallationCriteria::new())
+///     .expect("A VS installation should exist");
+/// let toolset = VsMsvcToolset::try_from(&installation).expect("A toolset should exist");
+/// let cl = toolset
+///     .bin(HostTarget::new(Arch::X64, Arch::Arm64))
+///     .expect("The arm64 cross compiler should exist")
+///     .join("cl.exe");
+/// ```
+#[derive(Debug, Clone)]
+pub struct VsMsvcToolset {
+    version: String,
+    dir: PathBuf,
+}
+
+impl VsMsvcToolset {
+    const TOOLS_DIR: &'static str = "VC/Tools/MSVC";
+    const DEFAULT_VERSION_FILE: &'static str =
+        "VC/Auxiliary/Build/Microsoft.VCToolsVersion.default.txt";
+    const BIN_DIR: &'static str = "bin";
+    const INCLUDE_DIR: &'static str = "include";
+    const LIB_DIR: &'static str = "lib";
+    const ATLMFC_DIR: &'static str = "atlmfc";
+
+    /// The version of the toolset e.g. `14.42.34433`.
+    pub fn version(&self) -> &str {
+        self.version.as_str()
+    }
+
+    /// The directory of the toolset i.e. `VC/Tools/MSVC/<version>`.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+
+    /// The directory containing the tools, e.g. `cl.exe` and `link.exe`,
+    /// that run on the host and produce binaries for the target
+    /// i.e. `bin/Host<host>/<target>`.
+    pub fn bin(&self, host_target: HostTarget) -> Result<PathBuf> {
+        sub_directory(
+            self.dir.as_path(),
+            format!(
+                "{}/{}/{}",
+                Self::BIN_DIR,
+                host_target.host().host_dir_name(),
+                host_target.target().dir_name()
+            )
+            .as_str(),
+        )
+    }
+
+    /// The directory containing the headers.
+    pub fn include(&self) -> Result<PathBuf> {
+        sub_directory(self.dir.as_path(), Self::INCLUDE_DIR)
+    }
+
+    /// The directory containing the libraries for the target
+    /// i.e. `lib/<target>`.
+    pub fn lib(&self, target: Arch) -> Result<PathBuf> {
+        sub_directory(
+            self.dir.as_path(),
+            format!("{}/{}", Self::LIB_DIR, target.dir_name()).as_str(),
+        )
+    }
+
+    /// The directory containing the ATL and MFC headers.
+    pub fn atlmfc_include(&self) -> Result<PathBuf> {
+        sub_directory(
+            self.dir.as_path(),
+            format!("{}/{}", Self::ATLMFC_DIR, Self::INCLUDE_DIR).as_str(),
+        )
+    }
+
+    /// The directory containing the ATL and MFC libraries for
+    /// the target i.e. `atlmfc/lib/<target>`.
+    pub fn atlmfc_lib(&self, target: Arch) -> Result<PathBuf> {
+        sub_directory(
+            self.dir.as_path(),
+            format!(
+                "{}/{}/{}",
+                Self::ATLMFC_DIR,
+                Self::LIB_DIR,
+                target.dir_name()
+            )
+            .as_str(),
+        )
+    }
+
+    /// Lists the toolsets of the VS installation ordered by version.
+    pub fn list(vs_installation: &VsInstallation) -> Result<Vec<Self>> {
+        Self::list_in_range(vs_installation, None, None)
+    }
+
+    /// Finds the toolset of the VS installation with the highest version
+    /// that is in a range between max (exclusive) and min (inclusive).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{InstallationCriteria, MsvcToolsetVersion, VsInstallation, VsMsvcToolset};
+    ///
+    /// let installation = VsInstallation::find(&InstallationCriteria::new())
+    ///     .expect("A VS installation should exist");
+    /// // Find the latest 14.3x toolset, i.e. v143.
+    /// let toolset = VsMsvcToolset::find_in_range(
+    ///     &installation,
+    ///     Some(MsvcToolsetVersion::parse("14.50").unwrap()),
+    ///     Some(MsvcToolsetVersion::parse("14.30").unwrap()),
+    /// );
+    /// ```
+    pub fn find_in_range(
+        vs_installation: &VsInstallation,
+        max: Option<MsvcToolsetVersion>,
+        min: Option<MsvcToolsetVersion>,
+    ) -> Result<Self> {
+        Self::list_in_range(vs_installation, max.as_ref(), min.as_ref()).and_then(|toolsets| {
+            toolsets.into_iter().last().ok_or_else(|| {
+                Error::msvc_toolset_not_found(format!(
+                    "No toolset in the version range {} was found in {}.",
+                    versions::describe_range(max.as_ref(), min.as_ref()),
+                    vs_installation.path().display()
+                ))
+            })
+        })
+    }
+
+    // Internal function that lists the toolsets of the VS installation
+    // that are in the version range ordered by version. Directories
+    // whose names are not versions are ignored.
+    fn list_in_range(
+        vs_installation: &VsInstallation,
+        max: Option<&MsvcToolsetVersion>,
+        min: Option<&MsvcToolsetVersion>,
+    ) -> Result<Vec<Self>> {
+        let tools_dir = sub_directory(vs_installation.path(), Self::TOOLS_DIR)?;
+        trace!("Searching for MSVC toolsets in {}", tools_dir.display());
+        let dirs = tools_dir
+            .read_dir()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.is_dir())
+            .collect::<Vec<PathBuf>>();
+        let mut versioned_dirs = dirs
+            .iter()
+            .filter_map(|dir| {
+                let name = dir.file_name()?.to_str()?;
+                let version = MsvcToolsetVersion::parse(name).ok()?;
+                Some((version, name, dir))
+            })
+            .filter(|(version, _, _)| version.is_in_range(max, min))
+            .collect::<Vec<_>>();
+        versioned_dirs.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+        Ok(versioned_dirs
+            .into_iter()
+            .map(|(_, name, dir)| Self {
+                version: name.to_string(),
+                dir: dir.clone(),
+            })
+            .collect())
+    }
+
+    /// The version of the default toolset of the VS installation read
+    /// from `VC/Auxiliary/Build/Microsoft.VCToolsVersion.default.txt`.
+    pub fn default_version(vs_installation: &VsInstallation) -> Option<String> {
+        let path = vs_installation.path().join(Self::DEFAULT_VERSION_FILE);
+        trace!(
+            "Reading the default MSVC toolset version from {}",
+            path.display()
+        );
+        std::fs::read_to_string(path)
+            .ok()
+            .map(|content| content.trim().to_string())
+            .filter(|version| !version.is_empty())
+    }
+}
+
+impl TryFrom<&VsInstallation> for VsMsvcToolset {
+    type Error = Error;
+
+    /// Selects the default toolset of the VS installation or, if the
+    /// default toolset can not be found, the toolset with the highest version.
+    fn try_from(vs_installation: &VsInstallation) -> Result<VsMsvcToolset> {
+        let default = Self::default_version(vs_installation).and_then(|version| {
+            sub_directory(vs_installation.path(), Self::TOOLS_DIR)
+                .and_then(|tools_dir| sub_directory(tools_dir.as_path(), version.as_str()))
+                .map(|dir| Self { version, dir })
+                .map_err(|e| warn!("Ignoring the default MSVC toolset: {}", e))
+                .ok()
+        });
+        match default {
+            Some(toolset) => Ok(toolset),
+            None => Self::find_in_range(vs_installation, None, None),
+        }
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    // Helper that creates an installation containing the toolsets.
+    fn installation_with_toolsets(versions: &[&str]) -> (TempDir, VsInstallation) {
+        let root = tempdir().expect("It should be possible to create a temporary directory.");
+        versions.iter().for_each(|version| {
+            [
+                "bin/Hostx64/x64",
+                "bin/Hostx64/arm64",
+                "include",
+                "lib/x64",
+                "lib/arm64",
+                "atlmfc/include",
+                "atlmfc/lib/x64",
+            ]
+            .iter()
+            .for_each(|dir| {
+                std::fs::create_dir_all(
+                    root.path()
+                        .join(VsMsvcToolset::TOOLS_DIR)
+                        .join(version)
+                        .join(dir),
+                )
+                .expect("It should be possible to create the toolset directories.")
+            })
+        });
+        let installation = VsInstallation::from_json(serde_json::json!({
+            "installationPath": root.path(),
+            "installationVersion": "17.12.35527.113",
+        }))
+        .expect("It should be possible to create the installation.");
+        (root, installation)
+    }
+
+    // Helper that writes the default toolset version file.
+    fn write_default_version(installation: &VsInstallation, content: &str) {
+        let path = installation
+            .path()
+            .join(VsMsvcToolset::DEFAULT_VERSION_FILE);
+        std::fs::create_dir_all(path.parent().unwrap())
+            .expect("It should be possible to create the directories.");
+        std::fs::write(path, content).expect("It should be possible to write the file.");
+    }
+
+    #[test]
+    fn test_list() {
+        let (_root, installation) =
+            installation_with_toolsets(&["14.42.34433", "14.29.30133", "14.38.33130"]);
+        std::fs::create_dir_all(installation.path().join("VC/Tools/MSVC/not-a-version"))
+            .expect("It should be possible to create the directory.");
+
+        let actual = VsMsvcToolset::list(&installation)
+            .expect("It should be possible to list the toolsets.")
+            .iter()
+            .map(|toolset| toolset.version().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(actual, ["14.29.30133", "14.38.33130", "14.42.34433"]);
+    }
+
+    #[test]
+    fn test_find_in_range() {
+        let (_root, installation) =
+            installation_with_toolsets(&["14.42.34433", "14.29.30133", "14.38.33130"]);
+
+        let actual = VsMsvcToolset::find_in_range(
+            &installation,
+            Some(MsvcToolsetVersion::parse("14.40").unwrap()),
+            Some(MsvcToolsetVersion::parse("14.30").unwrap()),
+        )
+        .expect("It should be possible to find a toolset in the range.");
+        assert_eq!(actual.version(), "14.38.33130");
+
+        let error = VsMsvcToolset::find_in_range(
+            &installation,
+            None,
+            Some(MsvcToolsetVersion::parse("14.50").unwrap()),
+        )
+        .expect_err("No toolset should be found above the highest version.");
+        assert!(matches!(error, Error::MsvcToolsetNotFound { .. }));
+    }
+
+    #[test]
+    fn test_try_from() {
+        let (_root, installation) = installation_with_toolsets(&["14.42.34433", "14.38.33130"]);
+
+        let actual = VsMsvcToolset::try_from(&installation)
+            .expect("It should be possible to find the latest toolset.");
+        assert_eq!(actual.version(), "14.42.34433");
+
+        write_default_version(&installation, "14.38.33130\r\n");
+        assert_eq!(
+            VsMsvcToolset::default_version(&installation).as_deref(),
+            Some("14.38.33130")
+        );
+        let actual = VsMsvcToolset::try_from(&installation)
+            .expect("It should be possible to find the default toolset.");
+        assert_eq!(actual.version(), "14.38.33130");
+
+        write_default_version(&installation, "14.40.33807");
+        let actual = VsMsvcToolset::try_from(&installation)
+            .expect("It should be possible to fall back to the latest toolset.");
+        assert_eq!(actual.version(), "14.42.34433");
+    }
+
+    #[test]
+    fn test_paths() {
+        let (_root, installation) = installation_with_toolsets(&["14.42.34433"]);
+        let toolset = VsMsvcToolset::try_from(&installation)
+            .expect("It should be possible to find the toolset.");
+        let dir = installation.path().join("VC/Tools/MSVC/14.42.34433");
+        assert_eq!(toolset.dir(), dir.as_path());
+
+        assert_eq!(
+            toolset
+                .bin(HostTarget::new(Arch::X64, Arch::Arm64))
+                .expect("It should be possible to find the cross compiler."),
+            dir.join("bin/Hostx64/arm64")
+        );
+        assert_eq!(
+            toolset
+                .include()
+                .expect("It should be possible to find the headers."),
+            dir.join("include")
+        );
+        assert_eq!(
+            toolset
+                .lib(Arch::X64)
+                .expect("It should be possible to find the libraries."),
+            dir.join("lib/x64")
+        );
+        assert_eq!(
+            toolset
+                .atlmfc_include()
+                .expect("It should be possible to find the ATL headers."),
+            dir.join("atlmfc/include")
+        );
+        assert_eq!(
+            toolset
+                .atlmfc_lib(Arch::X64)
+                .expect("It should be possible to find the ATL libraries."),
+            dir.join("atlmfc/lib/x64")
+        );
+        assert!(matches!(
+            toolset.bin(HostTarget::native(Arch::X86)),
+            Err(Error::MissingComponentDirectory { .. })
+        ));
+        assert!(toolset.atlmfc_lib(Arch::Arm64).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! Module for discovering VS installations without vswhere by reading
+//! the instance state stored by the VS setup configuration.
+use crate::{
+    error::{Error, Result},
+    installation_source::InstallationSource,
+    selection::InstallationCriteria,
+    vs_installation::VsInstallation,
+};
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Type for discovering VS installations by reading the `state.json`
+/// file of each instance in the setup instances directory, i.e.
+/// `%ProgramData%\Microsoft\VisualStudio\Packages\_Instances`.
+///
+/// This works without the VS installer, e.g. in container images that
+/// only contain extracted build tools, but the state does not contain
+/// whether an instance is complete or launchable.
+///
+/// # Examples
+///
+/// ```no_run
+/// use msbuild::{InstallationCriteria, VsSetupInstances};
+///
+/// let instances = VsSetupInstances::find().expect("The instances directory should exist");
+/// let installation = instances.find_installation(&InstallationCriteria::new());
+/// ```
+#[derive(Debug, Clone)]
+pub struct VsSetupInstances {
+    dir: PathBuf,
+}
+
+impl VsSetupInstances {
+    const PROGRAM_DATA_ENV_KEY: &'static str = "ProgramData";
+    const DEFAULT_PROGRAM_DATA: &'static str = "C:\\ProgramData";
+    const INSTANCES_DIR: &'static str = "Microsoft\\VisualStudio\\Packages\\_Instances";
+    const STATE_FILE: &'static str = "state.json";
+
+    /// Creates a VsSetupInstances object for the instances directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Creates a VsSetupInstances object for the instances directory
+    /// in `%ProgramData%` if it exists.
+    pub fn find() -> Result<Self> {
+        let program_data = std::env::var(Self::PROGRAM_DATA_ENV_KEY)
+            .unwrap_or(Self::DEFAULT_PROGRAM_DATA.to_string());
+        trace!("Consulted {}: {}", Self::PROGRAM_DATA_ENV_KEY, program_data);
+        let dir = Path::new(program_data.as_str()).join(Self::INSTANCES_DIR);
+        trace!("Probing for setup instances at {}", dir.display());
+        if dir.is_dir() {
+            Ok(Self::new(dir))
+        } else {
+            Err(Error::SetupInstancesNotFound { dir })
+        }
+    }
+
+    /// The instances directory.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+
+    /// Lists the installations in the instances directory ordered
+    /// by instance id. Instances without a readable `state.json`, e.g.
+    /// because the installation is in progress, are skipped.
+    pub fn list(&self) -> Result<Vec<VsInstallation>> {
+        VsInstallation::list_in(self)
+    }
+
+    /// Finds the installation in the instances directory that best
+    /// fulfills the criteria.
+    pub fn find_installation(&self, criteria: &InstallationCriteria) -> Result<VsInstallation> {
+        VsInstallation::find_in(self, criteria)
+    }
+}
+
+impl InstallationSource for VsSetupInstances {
+    // Reads the instances and converts them into the instance
+    // format used by vswhere.
+    fn instances(&self) -> Result<Vec<Value>> {
+        if !self.dir.is_dir() {
+            return Err(Error::SetupInstancesNotFound {
+                dir: self.dir.clone(),
+            });
+        }
+        let mut instance_dirs = self
+            .dir
+            .read_dir()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.is_dir())
+            .collect::<Vec<PathBuf>>();
+        instance_dirs.sort();
+        Ok(instance_dirs
+            .iter()
+            .filter_map(|instance_dir| {
+                let state_file = instance_dir.join(Self::STATE_FILE);
+                trace!("Reading {}", state_file.display());
+                let instance_id = instance_dir.file_name()?.to_str()?;
+                std::fs::read_to_string(&state_file)
+                    .map_err(Error::from)
+                    .and_then(|content| {
+                        serde_json::from_str::<Value>(&content).map_err(|e| Error::Json {
+                            reason: format!("Failed to parse {}.", state_file.display()),
+                            source: e,
+                        })
+                    })
+                    .map(|state| Self::to_vswhere_instance(instance_id, state))
+                    .map_err(|e| {
+                        warn!("Skipping the instance in {}: {}", instance_dir.display(), e);
+                    })
+                    .ok()
+            })
+            .collect())
+    }
+}
+
+impl VsSetupInstances {
+    // Internal function that converts the instance state into the
+    // instance format used by vswhere.
+    fn to_vswhere_instance(instance_id: &str, state: Value) -> Value {
+        let get = |key: &str| state.get(key).cloned();
+        let catalog = state.get("catalogInfo");
+        let product = state.get("product");
+        let resources = state
+            .get("localizedResources")
+            .and_then(Value::as_array)
+            .and_then(|resources| {
+                resources
+                    .iter()
+                    .find(|r| {
+                        r.get("language")
+                            .and_then(Value::as_str)
+                            .is_some_and(|l| l.eq_ignore_ascii_case("en-us"))
+                    })
+                    .or(resources.first())
+            });
+        let install_path = state.get("installationPath").and_then(Value::as_str);
+        let product_path = install_path.and_then(|path| {
+            state
+                .get("launchParams")
+                .and_then(|p| p.get("fileName"))
+                .and_then(Value::as_str)
+                .map(|file_name| format!("{}\\{}", path.trim_end_matches('\\'), file_name))
+        });
+        let is_prerelease = catalog
+            .and_then(|c| c.get("productMilestoneIsPreRelease"))
+            .and_then(Value::as_str)
+            .map(|v| v.eq_ignore_ascii_case("true"));
+
+        let mut instance = Map::new();
+        let mut insert = |key: &str, value: Option<Value>| {
+            if let Some(value) = value {
+                instance.insert(key.to_string(), value);
+            }
+        };
+        insert("instanceId", Some(Value::from(instance_id)));
+        insert("installDate", get("installDate"));
+        insert("installationName", get("installationName"));
+        insert("installationPath", get("installationPath"));
+        insert("installationVersion", get("installationVersion"));
+        insert("productId", product.and_then(|p| p.get("id")).cloned());
+        insert("productPath", product_path.map(Value::from));
+        insert("isPrerelease", is_prerelease.map(Value::from));
+        insert(
+            "displayName",
+            resources.and_then(|r| r.get("title")).cloned(),
+        );
+        insert(
+            "description",
+            resources.and_then(|r| r.get("description")).cloned(),
+        );
+        insert("channelId", get("channelId"));
+        insert("catalog", catalog.cloned());
+        insert("packages", get("packages").or(get("selectedPackages")));
+        Value::Object(instance)
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::selection::VsProduct;
+
+    // Helper that returns the fixture instances directory.
+    fn fixture_instances() -> VsSetupInstances {
+        VsSetupInstances::new(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/vs_instances"),
+        )
+    }
+
+    #[test]
+    fn test_list() {
+        let installations = fixture_instances()
+            .list()
+            .expect("It should be possible to list the fixture instances.");
+        // The instance with the truncated state.json is skipped.
+        assert_eq!(installations.len(), 2);
+
+        let build_tools = &installations[0];
+        assert_eq!(build_tools.instance_id(), Some("3f2b1c9a"));
+        assert_eq!(
+            build_tools.path(),
+            Path::new("C:\\Program Files (x86)\\Microsoft Visual Studio\\2022\\BuildTools")
+        );
+        assert_eq!(
+            build_tools.product_id(),
+            Some("Microsoft.VisualStudio.Product.BuildTools")
+        );
+        assert_eq!(
+            build_tools.display_name(),
+            Some("Visual Studio Build Tools 2022")
+        );
+        assert_eq!(build_tools.channel_id(), Some("VisualStudio.17.Release"));
+        assert_eq!(build_tools.is_prerelease(), Some(false));
+        assert_eq!(build_tools.is_complete(), None);
+        assert!(build_tools.has_package("Microsoft.VisualStudio.Component.VC.Tools.x86.x64"));
+        assert_eq!(
+            build_tools.catalog().and_then(|c| c.product_line_version()),
+            Some("2022")
+        );
+        assert_eq!(
+            build_tools.product_path(),
+            Some(Path::new(
+                "C:\\Program Files (x86)\\Microsoft Visual Studio\\2022\\BuildTools\\Common7\\Tools\\LaunchDevCmd.bat"
+            ))
+        );
+
+        let preview = &installations[1];
+        assert_eq!(preview.instance_id(), Some("7d4e8a21"));
+        assert_eq!(preview.is_prerelease(), Some(true));
+        assert!(preview.has_package("Microsoft.VisualStudio.Workload.ManagedDesktop"));
+    }
+
+    #[test]
+    fn test_find_installation() {
+        let instances = fixture_instances();
+        let installation = instances
+            .find_installation(&InstallationCriteria::new())
+            .expect("It should be possible to find the stable instance.");
+        assert_eq!(installation.instance_id(), Some("3f2b1c9a"));
+
+        let criteria = InstallationCriteria::new()
+            .allow_prerelease(true)
+            .include_product(VsProduct::Community);
+        let installation = instances
+            .find_installation(&criteria)
+            .expect("It should be possible to find the prerelease instance.");
+        assert_eq!(installation.instance_id(), Some("7d4e8a21"));
+    }
+
+    #[test]
+    fn test_list_missing_dir() {
+        let instances = VsSetupInstances::new(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/missing_instances"),
+        );
+        let error = instances
+            .list()
+            .expect_err("A missing instances directory should be an error.");
+        assert!(matches!(
+            &error,
+            Error::SetupInstancesNotFound { dir } if dir == instances.dir()
+        ));
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+}
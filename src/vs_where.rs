@@ -1,20 +1,32 @@
 //! Module that contains the code for providing the
 //! the `VsWhere.exe` binary functionality.
+use crate::{
+    error::{Error, Result},
+    installation_source::InstallationSource,
+    process::{self, CancellationToken, OutputStream, ProcessLimits},
+    vs_installation::VsInstallation,
+    vs_where_query::VsWhereQuery,
+};
+use serde_json::Value;
 use std::{
-    io::{Error, ErrorKind},
-    path::PathBuf,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 /// Type for finding and interacting with the
 /// vswhere executable.
 pub struct VsWhere {
     path: PathBuf,
+    limits: ProcessLimits,
 }
 
 impl VsWhere {
     const DEFAULT_PATH: &'static str =
         "C:\\Program Files (x86)\\Microsoft Visual Studio\\Installer\\vswhere.exe";
-    const ENV_KEY: &'static str = "VS_WHERE_PATH";
+    pub(crate) const ENV_KEY: &'static str = "VS_WHERE_PATH";
+    const EXE_NAME: &'static str = "vswhere.exe";
+    const INSTALLER_DIR: &'static str = "Microsoft Visual Studio\\Installer";
     const DEFAULT_ARGS: [&'static str; 6] = [
         "-legacy",
         "-prerelease",
@@ -25,40 +37,272 @@ impl VsWhere {
     ];
 
     /// Creates a VsWhere object if the `vswhere.exe`binary can be found.
-    pub fn find_vswhere() -> std::io::Result<Self> {
-        let path: PathBuf = VsWhere::vswhere_path();
-        if path.exists() {
-            Ok(VsWhere { path })
-        } else {
-            Err(Error::new(
-                ErrorKind::NotFound,
-                format!("The path [{}] does not exists.", path.to_string_lossy()),
-            ))
-        }
+    ///
+    /// If the `VS_WHERE_PATH` environment variable is set then only that
+    /// path is used. Otherwise the following locations are searched in order:
+    /// 1. The VS installer directory in `%ProgramFiles(x86)%` and `%ProgramFiles%`.
+    /// 2. The default path of the VS installer directory.
+    /// 3. The directories in `PATH`.
+    /// 4. The Chocolatey package locations in `%ChocolateyInstall%`
+    ///    or `%ProgramData%\chocolatey`.
+    /// 5. The NuGet package locations in `%NUGET_PACKAGES%` or
+    ///    `%USERPROFILE%\.nuget\packages`, the latest version first.
+    /// 6. The directory of the current executable.
+    ///
+    /// If vswhere can not be found [`Error::VsWhereNotFound`] is returned
+    /// containing all the probed paths.
+    pub fn find_vswhere() -> Result<Self> {
+        let probed =
+            VsWhere::search_paths(|key| std::env::var_os(key), std::env::current_exe().ok());
+        probed
+            .iter()
+            .find(|path| {
+                trace!("Probing for vswhere at {}", path.display());
+                path.is_file()
+            })
+            .map(|path| VsWhere {
+                path: path.clone(),
+                limits: ProcessLimits::default(),
+            })
+            .ok_or(Error::VsWhereNotFound { probed })
+    }
+
+    /// Sets the maximum time vswhere is allowed to run. When the time
+    /// is exceeded vswhere is killed and [`Error::TimedOut`] is returned.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a token that can be used to cancel the execution of vswhere
+    /// from another thread. When cancelled vswhere is killed and
+    /// [`Error::Cancelled`] is returned.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.limits.cancellation_token = Some(token);
+        self
     }
 
     /// Runs the executable with the provided argument
     /// or default argument if no arguments are provided.
-    pub fn run(self, args: Option<&[&str]>) -> std::io::Result<String> {
-        let command_args: &[&str] = args.unwrap_or(VsWhere::DEFAULT_ARGS.as_ref());
-        std::process::Command::new(self.path)
-            .args(command_args)
-            .output()
-            .and_then(|output: std::process::Output| {
-                std::str::from_utf8(&output.stdout).map_or_else(
-                    |e: std::str::Utf8Error| {
-                        Err(Error::new(
-                            ErrorKind::InvalidData,
-                            format!("Command output could not be parsed as UTF-8 ({}).", e),
-                        ))
-                    },
-                    |v: &str| Ok(v.to_string()),
+    pub fn run(self, args: Option<&[&str]>) -> Result<String> {
+        match args {
+            Some(args) => self.execute(args),
+            None => self.execute(VsWhere::DEFAULT_ARGS.as_ref()),
+        }
+    }
+
+    /// Runs the query and returns the instances that were found.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{VsWhere, VsWhereQuery};
+    ///
+    /// let vswhere = VsWhere::find_vswhere().expect("vswhere should exist");
+    /// let query = VsWhereQuery::new().products(["*"]).include_packages(true);
+    /// let installations = vswhere.query(&query).expect("The query should succeed");
+    /// ```
+    pub fn query(&self, query: &VsWhereQuery) -> Result<Vec<VsInstallation>> {
+        if query.outputs_values() {
+            return Err(Error::vswhere_output_invalid(
+                "The query outputs values instead of instances.",
+            ));
+        }
+        self.execute(query.to_args().as_slice())
+            .and_then(|output| VsInstallation::parse_from_json(&output))
+            .and_then(|instances| {
+                instances
+                    .into_iter()
+                    .map(VsInstallation::from_json)
+                    .collect()
+            })
+    }
+
+    /// Runs a query that outputs values, i.e. a query using
+    /// [`VsWhereQuery::find`] or [`VsWhereQuery::property`], and
+    /// returns the non-empty lines of the output.
+    pub fn query_values(&self, query: &VsWhereQuery) -> Result<Vec<String>> {
+        self.execute(query.to_args().as_slice()).map(|output| {
+            output
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+    }
+
+    /// Finds the files and directories inside the installation that
+    /// matches the pattern using the `-find` argument of vswhere.
+    /// See [`VsInstallation::find_files`] for a variant that does not
+    /// require vswhere.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use msbuild::{VsInstallation, VsWhere};
+    ///
+    /// let vswhere = VsWhere::find_vswhere().expect("vswhere should exist");
+    /// for installation in VsInstallation::list_all().expect("vswhere should exist") {
+    ///     let cl = vswhere.find_files(&installation, "VC\\Tools\\MSVC\\**\\x64\\cl.exe");
+    ///     println!("{}: {:?}", installation.path().display(), cl);
+    /// }
+    /// ```
+    pub fn find_files(&self, installation: &VsInstallation, pattern: &str) -> Result<Vec<PathBuf>> {
+        self.query_values(&VsWhereQuery::new().path(installation.path()).find(pattern))
+            .map(|values| values.into_iter().map(PathBuf::from).collect())
+    }
+
+    // Internal function that returns the instances found by the query
+    // followed by the legacy instances that the query did not find.
+    fn instances_of(&self, query: &VsWhereQuery) -> Result<Vec<Value>> {
+        // Legacy instances are identified by their instance id or,
+        // if they do not have one, by their installation path.
+        let key = |instance: &Value| {
+            instance
+                .get("instanceId")
+                .or_else(|| instance.get("installationPath"))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let mut instances = self
+            .execute(query.to_args().as_slice())
+            .and_then(|output| VsInstallation::parse_from_json(&output))?;
+        let legacy_instances = self
+            .execute(VsWhereQuery::legacy_instances().to_args().as_slice())
+            .and_then(|output| VsInstallation::parse_from_json(&output))?;
+        let keys = instances.iter().filter_map(key).collect::<Vec<String>>();
+        instances.extend(
+            legacy_instances
+                .into_iter()
+                .filter(|instance| key(instance).is_none_or(|k| !keys.contains(&k))),
+        );
+        Ok(instances)
+    }
+
+    // Internal function that executes vswhere with the arguments
+    // and returns the stdout. If vswhere exits with a non-zero
+    // status the stderr is returned in the error.
+    fn execute(&self, args: &[impl AsRef<OsStr> + std::fmt::Debug]) -> Result<String> {
+        debug!("Running {} with arguments {:?}", self.path.display(), args);
+        let mut stdout = String::new();
+        let mut stderr = Vec::new();
+        process::run_with_lines(
+            std::process::Command::new(self.path.as_path()).args(args),
+            &self.limits,
+            |stream, line| match stream {
+                OutputStream::Stdout => {
+                    stdout.push_str(line);
+                    stdout.push('\n');
+                }
+                OutputStream::Stderr => stderr.push(line.to_string()),
+            },
+        )
+        .and_then(|status| {
+            debug!("vswhere exited with {}", status);
+            if status.success() {
+                Ok(stdout)
+            } else {
+                Err(Error::VsWhereFailed {
+                    status,
+                    stderr: stderr.join("\n"),
+                })
+            }
+        })
+    }
+
+    // Internal function that returns the paths where vswhere is searched
+    // for, in order and without duplicates. The environment variables are
+    // read using `env` so the function can be tested.
+    fn search_paths(
+        env: impl Fn(&str) -> Option<OsString>,
+        current_exe: Option<PathBuf>,
+    ) -> Vec<PathBuf> {
+        let env_value = env(VsWhere::ENV_KEY);
+        trace!("Consulted {}: {:?}", VsWhere::ENV_KEY, env_value);
+        if let Some(path) = env_value {
+            return vec![PathBuf::from(path)];
+        }
+
+        let mut paths = Vec::new();
+        ["ProgramFiles(x86)", "ProgramFiles"]
+            .into_iter()
+            .filter_map(&env)
+            .for_each(|dir| {
+                paths.push(
+                    Path::new(&dir)
+                        .join(VsWhere::INSTALLER_DIR)
+                        .join(VsWhere::EXE_NAME),
                 )
+            });
+        paths.push(PathBuf::from(VsWhere::DEFAULT_PATH));
+        if let Some(path_value) = env("PATH") {
+            paths.extend(std::env::split_paths(&path_value).map(|dir| dir.join(VsWhere::EXE_NAME)));
+        }
+        if let Some(chocolatey_dir) = env("ChocolateyInstall")
+            .map(PathBuf::from)
+            .or_else(|| env("ProgramData").map(|dir| Path::new(&dir).join("chocolatey")))
+        {
+            paths.push(chocolatey_dir.join("bin").join(VsWhere::EXE_NAME));
+            paths.push(
+                chocolatey_dir
+                    .join("lib\\vswhere\\tools")
+                    .join(VsWhere::EXE_NAME),
+            );
+        }
+        if let Some(nuget_dir) = env("NUGET_PACKAGES")
+            .map(PathBuf::from)
+            .or_else(|| env("USERPROFILE").map(|dir| Path::new(&dir).join(".nuget\\packages")))
+        {
+            paths.extend(VsWhere::nuget_paths(nuget_dir.join("vswhere").as_path()));
+        }
+        if let Some(exe_dir) = current_exe.as_deref().and_then(Path::parent) {
+            paths.push(exe_dir.join(VsWhere::EXE_NAME));
+        }
+
+        let mut unique_paths: Vec<PathBuf> = Vec::with_capacity(paths.len());
+        paths.into_iter().for_each(|path| {
+            if !unique_paths.contains(&path) {
+                unique_paths.push(path);
+            }
+        });
+        unique_paths
+    }
+
+    // Internal function that returns the paths of vswhere in the
+    // versioned directories of the NuGet package, the latest version first.
+    fn nuget_paths(package_dir: &Path) -> Vec<PathBuf> {
+        let mut versioned_dirs = package_dir
+            .read_dir()
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|path| path.is_dir())
+                    .filter_map(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .and_then(|name| lenient_semver::parse(name).ok())
+                            .map(|version| (version, path.clone()))
+                    })
+                    .collect::<Vec<_>>()
             })
+            .unwrap_or_default();
+        versioned_dirs.sort_by(|(a, _), (b, _)| b.cmp(a));
+        versioned_dirs
+            .into_iter()
+            .map(|(_, dir)| dir.join("tools").join(VsWhere::EXE_NAME))
+            .collect()
     }
+}
 
-    fn vswhere_path() -> PathBuf {
-        PathBuf::from(std::env::var(VsWhere::ENV_KEY).unwrap_or(VsWhere::DEFAULT_PATH.to_string()))
+impl InstallationSource for VsWhere {
+    fn instances(&self) -> Result<Vec<Value>> {
+        self.instances_of(&VsWhereQuery::all_instances())
+    }
+
+    fn instances_with_packages(&self) -> Result<Vec<Value>> {
+        self.instances_of(&VsWhereQuery::all_instances().include_packages(true))
     }
 }
 
@@ -67,10 +311,296 @@ impl VsWhere {
 // ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 #[cfg(test)]
 mod test {
+    use super::*;
+    use crate::selection::{CandidateStatus, InstallationCriteria, RejectionReason};
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_vswhere_search_paths_env_key() {
+        let env = HashMap::from([
+            ("VS_WHERE_PATH", "D:\\tools\\vswhere.exe"),
+            ("ProgramFiles", "C:\\Program Files"),
+        ]);
+        let actual = VsWhere::search_paths(|key| env.get(key).map(OsString::from), None);
+        assert_eq!(actual, [PathBuf::from("D:\\tools\\vswhere.exe")]);
+    }
+
+    #[test]
+    fn test_vswhere_search_paths() {
+        let nuget_dir = tempdir().expect("It should be possible to create a temporary directory.");
+        ["2.8.4", "3.1.7", "not-a-version"].iter().for_each(|v| {
+            std::fs::create_dir_all(nuget_dir.path().join("vswhere").join(v))
+                .expect("It should be possible to create the package directory.")
+        });
+        let path_value = std::env::join_paths(["bin1", "bin2"])
+            .expect("It should be possible to join the paths.");
+        let env = HashMap::from([
+            (
+                "ProgramFiles(x86)",
+                OsString::from("D:\\Program Files (x86)"),
+            ),
+            ("ProgramFiles", OsString::from("C:\\Program Files")),
+            ("PATH", path_value),
+            ("ProgramData", OsString::from("C:\\ProgramData")),
+            (
+                "NUGET_PACKAGES",
+                nuget_dir.path().as_os_str().to_os_string(),
+            ),
+        ]);
+        let actual = VsWhere::search_paths(
+            |key| env.get(key).cloned(),
+            Some(PathBuf::from("target").join("build.exe")),
+        );
+        let expected = [
+            Path::new("D:\\Program Files (x86)")
+                .join(VsWhere::INSTALLER_DIR)
+                .join(VsWhere::EXE_NAME),
+            Path::new("C:\\Program Files")
+                .join(VsWhere::INSTALLER_DIR)
+                .join(VsWhere::EXE_NAME),
+            PathBuf::from(VsWhere::DEFAULT_PATH),
+            PathBuf::from("bin1").join(VsWhere::EXE_NAME),
+            PathBuf::from("bin2").join(VsWhere::EXE_NAME),
+            Path::new("C:\\ProgramData")
+                .join("chocolatey")
+                .join("bin")
+                .join(VsWhere::EXE_NAME),
+            Path::new("C:\\ProgramData")
+                .join("chocolatey")
+                .join("lib\\vswhere\\tools")
+                .join(VsWhere::EXE_NAME),
+            nuget_dir
+                .path()
+                .join("vswhere")
+                .join("3.1.7")
+                .join("tools")
+                .join(VsWhere::EXE_NAME),
+            nuget_dir
+                .path()
+                .join("vswhere")
+                .join("2.8.4")
+                .join("tools")
+                .join(VsWhere::EXE_NAME),
+            PathBuf::from("target").join(VsWhere::EXE_NAME),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_vswhere_query() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let vswhere = fake_vswhere(
+            dir.path(),
+            r#"[{"installationPath": "C:\\VS\\2022\\BuildTools", "installationVersion": "17.12.35506.116"}]"#,
+        );
+        let query = VsWhereQuery::new().products(["*"]);
+        let installations = vswhere
+            .query(&query)
+            .expect("It should be possible to query the instances.");
+        assert_eq!(installations.len(), 1);
+        assert_eq!(
+            installations[0].path(),
+            Path::new("C:\\VS\\2022\\BuildTools")
+        );
+
+        // The query can be reused.
+        assert_eq!(
+            vswhere
+                .query(&query)
+                .expect("It should be possible to query the instances again.")
+                .len(),
+            1
+        );
+        assert!(matches!(
+            vswhere.query(&query.property("installationPath")),
+            Err(Error::VsWhereOutputInvalid { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_vswhere_query_values() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let vswhere = fake_vswhere(
+            dir.path(),
+            "C:\\VS\\2022\\BuildTools\n\nC:\\VS\\2022\\Community",
+        );
+        let values = vswhere
+            .query_values(&VsWhereQuery::new().property("installationPath"))
+            .expect("It should be possible to query the values.");
+        assert_eq!(
+            values,
+            ["C:\\VS\\2022\\BuildTools", "C:\\VS\\2022\\Community"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_vswhere_find_files() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let vswhere = fake_vswhere(
+            dir.path(),
+            "C:\\VS\\2022\\BuildTools\\Common7\\IDE\\devenv.com",
+        );
+        let installation: VsInstallation = serde_json::from_value(serde_json::json!({
+            "installationPath": "C:\\VS\\2022\\BuildTools",
+            "installationVersion": "17.12.35506.116",
+        }))
+        .expect("It should be possible to deserialize the installation.");
+        let files = vswhere
+            .find_files(&installation, "**\\devenv.com")
+            .expect("It should be possible to find the files.");
+        assert_eq!(
+            files,
+            [PathBuf::from(
+                "C:\\VS\\2022\\BuildTools\\Common7\\IDE\\devenv.com"
+            )]
+        );
+    }
+
     #[ignore]
     #[test]
     fn test_vswhere_find_vswhere_internal() {
         // Cannot run the tests unless vswhere has
         // been installed into the test environment.
     }
+
+    // Helper that creates a script that acts as vswhere by
+    // printing the output.
+    #[cfg(unix)]
+    fn fake_vswhere(dir: &Path, output: &str) -> VsWhere {
+        scripted_vswhere(dir, &format!("cat <<'EOF'\n{}\nEOF\n", output))
+    }
+
+    // Helper that creates a fake vswhere executing the shell script.
+    #[cfg(unix)]
+    fn scripted_vswhere(dir: &Path, script: &str) -> VsWhere {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("vswhere");
+        std::fs::write(&path, format!("#!/bin/sh\n{}", script))
+            .expect("It should be possible to create the fake vswhere.");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .expect("It should be possible to make the fake vswhere executable.");
+        VsWhere {
+            path,
+            limits: ProcessLimits::default(),
+        }
+    }
+
+    // Script for a fake vswhere that, like vswhere, rejects `-legacy`
+    // together with `-products`, `-requires` and `-include`, and only reports the
+    // packages with `-include packages`.
+    #[cfg(unix)]
+    const FAKE_VSWHERE_SCRIPT: &str = r#"
+case "$*" in
+*-legacy*-products*|*-legacy*-requires*|*-legacy*-include*)
+    echo 'Error 0x57: The -legacy parameter is incompatible with -products, -requires and -include' >&2
+    exit 87;;
+*-legacy*)
+    printf '%s\n' '[{"instanceId": "VisualStudio.14.0", "installationPath": "C:\\VS14", "installationVersion": "14.0"},'
+    printf '%s\n' ' {"instanceId": "a1b2c3d4", "installationPath": "C:\\VS\\Community", "installationVersion": "17.12.35506.116", "productId": "Microsoft.VisualStudio.Product.Community"}]';;
+*packages*)
+    printf '%s\n' '[{"instanceId": "a1b2c3d4", "installationPath": "C:\\VS\\Community", "installationVersion": "17.12.35506.116", "productId": "Microsoft.VisualStudio.Product.Community",'
+    printf '%s\n' '  "packages": [{"id": "Microsoft.VisualStudio.Workload.ManagedDesktop", "type": "Workload"}]},'
+    printf '%s\n' ' {"instanceId": "3f2b1c9a", "installationPath": "C:\\VS\\BuildTools", "installationVersion": "17.10.35013.160", "productId": "Microsoft.VisualStudio.Product.BuildTools",'
+    printf '%s\n' '  "packages": [{"id": "Microsoft.VisualStudio.Component.VC.Tools.x86.x64", "type": "Component"}]}]';;
+*)
+    printf '%s\n' '[{"instanceId": "a1b2c3d4", "installationPath": "C:\\VS\\Community", "installationVersion": "17.12.35506.116", "productId": "Microsoft.VisualStudio.Product.Community"},'
+    printf '%s\n' ' {"instanceId": "3f2b1c9a", "installationPath": "C:\\VS\\BuildTools", "installationVersion": "17.10.35013.160", "productId": "Microsoft.VisualStudio.Product.BuildTools"}]';;
+esac
+"#;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_instances() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let vswhere = scripted_vswhere(dir.path(), FAKE_VSWHERE_SCRIPT);
+
+        let instances = vswhere
+            .instances()
+            .expect("It should be possible to query the instances.");
+        let ids = instances
+            .iter()
+            .map(|instance| instance["instanceId"].as_str().unwrap_or_default())
+            .collect::<Vec<&str>>();
+        assert_eq!(ids, ["a1b2c3d4", "3f2b1c9a", "VisualStudio.14.0"]);
+        assert!(instances
+            .iter()
+            .all(|instance| instance.get("packages").is_none()));
+
+        let instances = vswhere
+            .instances_with_packages()
+            .expect("It should be possible to query the instances with packages.");
+        assert_eq!(instances.len(), 3);
+        assert!(instances[..2]
+            .iter()
+            .all(|instance| instance.get("packages").is_some()));
+
+        let criteria = InstallationCriteria::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64");
+        let installation = VsInstallation::find_in(&vswhere, &criteria)
+            .expect("It should be possible to find the installation with the component.");
+        assert_eq!(installation.instance_id(), Some("3f2b1c9a"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_explain_lists_missing_packages() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let vswhere = scripted_vswhere(dir.path(), FAKE_VSWHERE_SCRIPT);
+
+        let criteria = InstallationCriteria::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64");
+        let report = VsInstallation::explain_in(&vswhere, &criteria)
+            .expect("It should be possible to explain the selection.");
+        let statuses = report
+            .candidates()
+            .iter()
+            .map(|candidate| (candidate.instance_id(), candidate.status().clone()))
+            .collect::<Vec<(Option<&str>, CandidateStatus)>>();
+        assert_eq!(
+            statuses,
+            [
+                (
+                    Some("a1b2c3d4"),
+                    CandidateStatus::Rejected(RejectionReason::MissingPackages(vec![
+                        "Microsoft.VisualStudio.Component.VC.Tools.x86.x64".to_string()
+                    ]))
+                ),
+                (Some("3f2b1c9a"), CandidateStatus::Selected),
+                (Some("VisualStudio.14.0"), CandidateStatus::Accepted),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_failed() {
+        let dir = tempdir().expect("It should be possible to create a temporary directory.");
+        let vswhere = scripted_vswhere(
+            dir.path(),
+            "echo '[]'\necho 'Error 0x57: Unknown parameter: -requiresAll' >&2\nexit 87\n",
+        );
+
+        let error = vswhere
+            .execute(&["-requiresAll"])
+            .expect_err("A non-zero exit status should be an error.");
+        assert!(matches!(
+            &error,
+            Error::VsWhereFailed { status, stderr }
+                if status.code() == Some(87)
+                    && stderr == "Error 0x57: Unknown parameter: -requiresAll"
+        ));
+        assert!(matches!(
+            vswhere.instances(),
+            Err(Error::VsWhereFailed { .. })
+        ));
+        assert!(matches!(
+            vswhere.query_values(&VsWhereQuery::new().property("installationPath")),
+            Err(Error::VsWhereFailed { .. })
+        ));
+    }
 }
@@ -0,0 +1,295 @@
+//! Module containing a typed builder for the
+//! arguments of the `vswhere.exe` binary.
+use std::path::PathBuf;
+
+/// Builder for a vswhere query.
+///
+/// The query only describes the arguments so it can be reused
+/// and executed several times using [`crate::VsWhere::query`] or,
+/// when using [`VsWhereQuery::find`] or [`VsWhereQuery::property`],
+/// [`crate::VsWhere::query_values`].
+///
+/// # Examples
+///
+/// ```
+/// use msbuild::VsWhereQuery;
+///
+/// let query = VsWhereQuery::new()
+///     .version("[17.0,18.0)")
+///     .products(["*"])
+///     .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64")
+///     .latest(true);
+/// assert_eq!(
+///     query.to_args(),
+///     [
+///         "-latest",
+///         "-products",
+///         "*",
+///         "-requires",
+///         "Microsoft.VisualStudio.Component.VC.Tools.x86.x64",
+///         "-version",
+///         "[17.0,18.0)",
+///         "-format",
+///         "json",
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VsWhereQuery {
+    version: Option<String>,
+    products: Vec<String>,
+    requires: Vec<String>,
+    requires_any: bool,
+    latest: bool,
+    prerelease: bool,
+    legacy: bool,
+    sort: bool,
+    include_packages: bool,
+    path: Option<PathBuf>,
+    find: Option<String>,
+    property: Option<String>,
+}
+
+impl VsWhereQuery {
+    /// Creates a query that uses the vswhere defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a query for all the instances of all the products,
+    /// including prerelease instances, but not legacy instances.
+    pub(crate) fn all_instances() -> Self {
+        Self::new().prerelease(true).products(["*"])
+    }
+
+    /// Creates a query that also finds the legacy instances. vswhere does
+    /// not support `-legacy` together with `-products` and `-requires` so
+    /// the legacy instances are queried separately.
+    pub(crate) fn legacy_instances() -> Self {
+        Self::new().legacy(true).prerelease(true)
+    }
+
+    /// Only find instances in the version range e.g. `[17.0,18.0)`
+    /// (`-version`).
+    pub fn version(mut self, range: impl Into<String>) -> Self {
+        self.version = Some(range.into());
+        self
+    }
+
+    /// Only find instances of the product ids, `*` for all products
+    /// (`-products`). By default vswhere only finds Community,
+    /// Professional and Enterprise.
+    pub fn products<I, S>(mut self, product_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.products = product_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only find instances with the workload or component (`-requires`).
+    pub fn require(mut self, package_id: impl Into<String>) -> Self {
+        self.requires.push(package_id.into());
+        self
+    }
+
+    /// Find instances with any, instead of all, of the required workloads
+    /// and components (`-requiresAny`).
+    pub fn requires_any(mut self, requires_any: bool) -> Self {
+        self.requires_any = requires_any;
+        self
+    }
+
+    /// Only find the latest instance (`-latest`).
+    pub fn latest(mut self, latest: bool) -> Self {
+        self.latest = latest;
+        self
+    }
+
+    /// Also find prerelease instances (`-prerelease`).
+    pub fn prerelease(mut self, prerelease: bool) -> Self {
+        self.prerelease = prerelease;
+        self
+    }
+
+    /// Also find VS 2015 and older instances (`-legacy`).
+    pub fn legacy(mut self, legacy: bool) -> Self {
+        self.legacy = legacy;
+        self
+    }
+
+    /// Sort the instances from newest version and last installed
+    /// (`-sort`).
+    pub fn sort(mut self, sort: bool) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Include the packages of the instances (`-include packages`).
+    pub fn include_packages(mut self, include_packages: bool) -> Self {
+        self.include_packages = include_packages;
+        self
+    }
+
+    /// Only find the instance that contains the path (`-path`).
+    /// vswhere does not allow this to be combined with other options
+    /// that selects instances.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Find files matching the pattern, e.g. `**\cl.exe`, inside
+    /// the instances (`-find`).
+    pub fn find(mut self, pattern: impl Into<String>) -> Self {
+        self.find = Some(pattern.into());
+        self
+    }
+
+    /// Only output the value of the property, e.g. `installationPath`,
+    /// of the instances (`-property`).
+    pub fn property(mut self, name: impl Into<String>) -> Self {
+        self.property = Some(name.into());
+        self
+    }
+
+    /// Whether or not the query outputs values, i.e. files or a
+    /// property, instead of instances.
+    pub fn outputs_values(&self) -> bool {
+        self.find.is_some() || self.property.is_some()
+    }
+
+    /// Renders the query as vswhere arguments. Queries for instances
+    /// use the json format and queries for values use the value format.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        [
+            (self.legacy, "-legacy"),
+            (self.prerelease, "-prerelease"),
+            (self.latest, "-latest"),
+            (self.sort, "-sort"),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .for_each(|(_, arg)| args.push(arg.to_string()));
+        if !self.products.is_empty() {
+            args.push("-products".to_string());
+            args.extend(self.products.iter().cloned());
+        }
+        if !self.requires.is_empty() {
+            args.push("-requires".to_string());
+            args.extend(self.requires.iter().cloned());
+            if self.requires_any {
+                args.push("-requiresAny".to_string());
+            }
+        }
+        if let Some(version) = &self.version {
+            args.push("-version".to_string());
+            args.push(version.clone());
+        }
+        if let Some(path) = &self.path {
+            args.push("-path".to_string());
+            args.push(path.to_string_lossy().into_owned());
+        }
+        if self.include_packages {
+            args.push("-include".to_string());
+            args.push("packages".to_string());
+        }
+        if let Some(pattern) = &self.find {
+            args.push("-find".to_string());
+            args.push(pattern.clone());
+        }
+        if let Some(name) = &self.property {
+            args.push("-property".to_string());
+            args.push(name.clone());
+        }
+        args.push("-format".to_string());
+        args.push(
+            if self.outputs_values() {
+                "value"
+            } else {
+                "json"
+            }
+            .to_string(),
+        );
+        args
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// Unit tests of the private functions and methods
+// ////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_args_all_instances() {
+        assert_eq!(
+            VsWhereQuery::all_instances().to_args(),
+            ["-prerelease", "-products", "*", "-format", "json"]
+        );
+        assert_eq!(
+            VsWhereQuery::all_instances()
+                .include_packages(true)
+                .to_args(),
+            [
+                "-prerelease",
+                "-products",
+                "*",
+                "-include",
+                "packages",
+                "-format",
+                "json"
+            ]
+        );
+        assert_eq!(
+            VsWhereQuery::legacy_instances().to_args(),
+            ["-legacy", "-prerelease", "-format", "json"]
+        );
+    }
+
+    #[test]
+    fn test_to_args_values() {
+        let query = VsWhereQuery::new()
+            .require("Microsoft.VisualStudio.Component.VC.Tools.x86.x64")
+            .require("Microsoft.VisualStudio.Component.VC.Tools.ARM64")
+            .requires_any(true)
+            .sort(true)
+            .find("VC\\Tools\\MSVC\\**\\cl.exe");
+        assert!(query.outputs_values());
+        assert_eq!(
+            query.to_args(),
+            [
+                "-sort",
+                "-requires",
+                "Microsoft.VisualStudio.Component.VC.Tools.x86.x64",
+                "Microsoft.VisualStudio.Component.VC.Tools.ARM64",
+                "-requiresAny",
+                "-find",
+                "VC\\Tools\\MSVC\\**\\cl.exe",
+                "-format",
+                "value"
+            ]
+        );
+        assert_eq!(
+            VsWhereQuery::new()
+                .path("C:\\VS\\2022\\BuildTools")
+                .find("**\\link.exe")
+                .to_args(),
+            [
+                "-path",
+                "C:\\VS\\2022\\BuildTools",
+                "-find",
+                "**\\link.exe",
+                "-format",
+                "value"
+            ]
+        );
+        assert_eq!(
+            VsWhereQuery::new().property("installationPath").to_args(),
+            ["-property", "installationPath", "-format", "value"]
+        );
+    }
+}
@@ -1,11 +1,15 @@
 //! Module that contains functionality for programtically
 //! retrieve information about the windows SDKs available on
 //! the system.
-use crate::{versions::WinSdkVersion, vs_paths::sub_directory};
+pub use crate::versions::WinSdkVersion;
+use crate::{
+    arch::Arch,
+    error::{Error, Result},
+    vs_paths::sub_directory,
+};
 use std::{
     collections::BTreeMap,
     fs::DirEntry,
-    io::{Error, ErrorKind},
     path::{Path, PathBuf},
 };
 
@@ -35,7 +39,7 @@ impl WinSdkIncludes {
     ];
 
     /// Creates a WinSdkInclude object from include path.
-    pub fn create(include_path: &Path) -> std::io::Result<Self> {
+    pub fn create(include_path: &Path) -> Result<Self> {
         Ok(Self {
             cppwinrt: sub_directory(include_path, Self::CPPWINRT_DIR)?,
             shared: sub_directory(include_path, Self::SHARED_DIR)?,
@@ -74,29 +78,83 @@ impl WinSdkIncludes {
 
 /// Struct holding information regarding the Windows SDK.
 pub struct WinSdk {
+    dir: PathBuf,
+    version: String,
     include: WinSdkIncludes,
 }
 
 impl WinSdk {
-    const ENV_KEY: &'static str = "WIN_SDK_PATH";
+    pub(crate) const ENV_KEY: &'static str = "WIN_SDK_PATH";
+    #[cfg(windows)]
     const REG_PATH: &'static str =
         "SOFTWARE\\WOW6432Node\\Microsoft\\Microsoft SDKs\\Windows\\v10.0";
+    #[cfg(windows)]
     const HKLM: winreg::RegKey = winreg::RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE);
 
     pub const fn include_dirs(&self) -> &WinSdkIncludes {
         &self.include
     }
 
+    /// The installation folder of the SDK e.g.
+    /// `C:\Program Files (x86)\Windows Kits\10`.
+    pub fn dir(&self) -> &Path {
+        self.dir.as_path()
+    }
+
+    /// The version of the SDK e.g. `10.0.22621.0`.
+    pub fn version(&self) -> &str {
+        self.version.as_str()
+    }
+
+    /// The directory containing the universal CRT libraries
+    /// for the target i.e. `Lib/<version>/ucrt/<target>`.
+    pub fn ucrt_lib_dir(&self, target: Arch) -> Result<PathBuf> {
+        self.versioned_sub_directory("Lib", &["ucrt", target.dir_name()])
+    }
+
+    /// The directory containing the user mode libraries
+    /// for the target i.e. `Lib/<version>/um/<target>`.
+    pub fn um_lib_dir(&self, target: Arch) -> Result<PathBuf> {
+        self.versioned_sub_directory("Lib", &["um", target.dir_name()])
+    }
+
+    /// The directory containing the tools, e.g. `rc.exe`, that run
+    /// on the host i.e. `bin/<version>/<host>`.
+    pub fn bin_dir(&self, host: Arch) -> Result<PathBuf> {
+        self.versioned_sub_directory("bin", &[host.dir_name()])
+    }
+
+    /// The directory containing the windows metadata i.e.
+    /// `UnionMetadata/<version>`.
+    pub fn union_metadata_dir(&self) -> Result<PathBuf> {
+        self.versioned_sub_directory("UnionMetadata", &[])
+    }
+
+    /// The directory containing the API contract references i.e.
+    /// `References/<version>`.
+    pub fn references_dir(&self) -> Result<PathBuf> {
+        self.versioned_sub_directory("References", &[])
+    }
+
+    // Internal function that constructs the verified path of a sub
+    // directory of the versioned directory in the parent directory.
+    fn versioned_sub_directory(&self, parent: &str, dirs: &[&str]) -> Result<PathBuf> {
+        let dir = [parent, self.version.as_str()]
+            .iter()
+            .chain(dirs.iter())
+            .copied()
+            .collect::<Vec<&str>>()
+            .join("/");
+        sub_directory(self.dir.as_path(), dir.as_str())
+    }
+
     // Finds a Windows SDK.
-    pub fn find() -> std::io::Result<Self> {
+    pub fn find() -> Result<Self> {
         Self::find_in_range(None, None)
     }
 
     /// Finds a Windows SDK in the specified version range.
-    pub fn find_in_range(
-        max: Option<WinSdkVersion>,
-        min: Option<WinSdkVersion>,
-    ) -> std::io::Result<Self> {
+    pub fn find_in_range(max: Option<WinSdkVersion>, min: Option<WinSdkVersion>) -> Result<Self> {
         // Each folder of intresst conatins folders with a version as the name.
         // If other folders are of interesst then the versions must match.
         // |-- Include
@@ -106,31 +164,44 @@ impl WinSdk {
         // |    |-- 10.0.a.0
         // In the case above the only option would be 10.0.a.0 and if that version
         // is not in the version range then no WinSdk would be found.
-        let installation_folder = Self::installation_folder()?;
-        let include_versioned_dirs = Self::include_versioned_subdirs(
-            installation_folder.as_path(),
-            max.as_ref(),
-            min.as_ref(),
-        )?;
-
-        Self::select_sdk(include_versioned_dirs)
+        Self::installation_folder()
+            .and_then(|installation_folder| Self::find_in_dir(&installation_folder, max, min))
+    }
+
+    /// Finds a Windows SDK in the specified version range inside
+    /// the installation folder e.g. `C:\Program Files (x86)\Windows Kits\10`.
+    pub fn find_in_dir(
+        installation_folder: &Path,
+        max: Option<WinSdkVersion>,
+        min: Option<WinSdkVersion>,
+    ) -> Result<Self> {
+        let include_versioned_dirs =
+            Self::include_versioned_subdirs(installation_folder, max.as_ref(), min.as_ref())?;
+
+        Self::select_sdk(installation_folder, include_versioned_dirs)
     }
 
     // Checks the version in all the interessting directories and selects
     // the latest common version.
-    fn select_sdk(versioned_include_dirs: Vec<PathBuf>) -> std::io::Result<Self> {
+    fn select_sdk(
+        installation_folder: &Path,
+        versioned_include_dirs: Vec<PathBuf>,
+    ) -> Result<Self> {
         let versioned_include_dirs_map =
             Self::versioned_directory_map(versioned_include_dirs.as_slice());
         // Unwrap is safe here the map cannot be empty.
         let (_, d) = versioned_include_dirs_map.last_key_value().unwrap();
 
         Ok(Self {
+            dir: installation_folder.to_path_buf(),
+            // It is ok to unwrap the name has already been parsed as a version.
+            version: d.file_name().and_then(|o| o.to_str()).unwrap().to_string(),
             include: WinSdkIncludes::create(d.as_path())?,
         })
     }
 
     /// Creates a map that maps SDK versions to directories.
-    fn versioned_directory_map(version_dirs: &[PathBuf]) -> BTreeMap<WinSdkVersion, &PathBuf> {
+    fn versioned_directory_map(version_dirs: &[PathBuf]) -> BTreeMap<WinSdkVersion<'_>, &PathBuf> {
         version_dirs
             .iter()
             .map(|d| {
@@ -151,8 +222,12 @@ impl WinSdk {
         parent: &Path,
         max: Option<&WinSdkVersion>,
         min: Option<&WinSdkVersion>,
-    ) -> std::io::Result<Vec<PathBuf>> {
+    ) -> Result<Vec<PathBuf>> {
         let search_dir = sub_directory(parent, "Include")?;
+        trace!(
+            "Searching for Windows SDK includes in {}",
+            search_dir.display()
+        );
         // Filter out Paths that are not dirs
         // and Paths where the ending cannot be parsed
         // as WinSdkVersion.
@@ -164,10 +239,7 @@ impl WinSdk {
             .filter(|path| WinSdkIncludes::is_valid(path))
             .collect::<Vec<PathBuf>>();
         if found.is_empty() {
-            return Err(Error::new(
-            ErrorKind::NotFound,
-            format!("No versioned `Include` directories in the specified version range were found inside `{}` dir.", search_dir.to_string_lossy()),
-        ));
+            return Err(Error::win_sdk_not_found(format!("No versioned `Include` directories in the specified version range were found inside `{}` dir.", search_dir.to_string_lossy())));
         }
         Ok(found)
     }
@@ -190,21 +262,22 @@ impl WinSdk {
         path.file_name()
             .and_then(|ver_dir| ver_dir.to_str())
             .and_then(|ver_dir_str| WinSdkVersion::parse(ver_dir_str).ok())
-            .map_or(false, |win_sdk_ver| win_sdk_ver.is_in_range(max, min))
+            .is_some_and(|win_sdk_ver| win_sdk_ver.is_in_range(max, min))
     }
 
-    fn installation_folder() -> std::io::Result<PathBuf> {
+    fn installation_folder() -> Result<PathBuf> {
         Self::installation_folder_environment_variable()
             .unwrap_or_else(Self::installation_folder_from_registry)
     }
 
     /// Extracts the installation folder from the environment variable.
-    fn installation_folder_environment_variable() -> Option<std::io::Result<PathBuf>> {
-        std::env::var(WinSdk::ENV_KEY).ok().map(|s| {
+    fn installation_folder_environment_variable() -> Option<Result<PathBuf>> {
+        let env_value = std::env::var(WinSdk::ENV_KEY).ok();
+        trace!("Consulted {}: {:?}", WinSdk::ENV_KEY, env_value);
+        env_value.map(|s| {
             let path = PathBuf::from(s);
             if !path.is_dir() {
-                return Err(Error::new(
-                    ErrorKind::InvalidData,
+                return Err(Error::win_sdk_not_found(
                     "`WIN_SDK_PATH` environment variable contained invalid data.",
                 ));
             }
@@ -213,20 +286,38 @@ impl WinSdk {
     }
 
     /// Extracts the installation folder from the Windows registry.
-    fn installation_folder_from_registry() -> std::io::Result<PathBuf> {
+    #[cfg(not(windows))]
+    fn installation_folder_from_registry() -> Result<PathBuf> {
+        Err(Error::win_sdk_not_found(format!(
+            "The registry is only available on Windows, use the `{}` environment variable instead.",
+            WinSdk::ENV_KEY
+        )))
+    }
+
+    /// Extracts the installation folder from the Windows registry.
+    #[cfg(windows)]
+    fn installation_folder_from_registry() -> Result<PathBuf> {
         Self::HKLM
             .open_subkey(Self::REG_PATH)
             .and_then(|sdk_entry| sdk_entry.get_value("InstallationFolder"))
+            .map_err(|e| Error::WinSdkNotFound {
+                reason: format!(
+                    "Failed to read `InstallationFolder` from `{}`.",
+                    Self::REG_PATH
+                ),
+                source: Some(e),
+            })
             .and_then(|path_string: String| {
+                trace!(
+                    "Read InstallationFolder `{}` from the registry.",
+                    path_string
+                );
                 let path = Path::new(path_string.as_str());
                 if !path.is_dir() {
-                    return Err(Error::new(
-                        ErrorKind::NotFound,
-                        format!(
-                            "The InstallationFolder `{}` does not exist.",
-                            path_string.as_str()
-                        ),
-                    ));
+                    return Err(Error::win_sdk_not_found(format!(
+                        "The InstallationFolder `{}` does not exist.",
+                        path_string.as_str()
+                    )));
                 }
                 Ok(PathBuf::from(path_string))
             })
@@ -248,7 +339,7 @@ mod test {
         let error = WinSdkIncludes::create(&invalid_path).expect_err(
             "Creating a WinSdkIncludes object with an invalid path should result in an error.",
         );
-        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert!(matches!(error, Error::MissingComponentDirectory { .. }));
     }
 
     #[test]
@@ -0,0 +1,122 @@
+use msbuild::diagnostics::{self, Severity};
+use std::path::Path;
+
+const CPP_FAILURE: &str = include_str!("fixtures/msbuild_cpp_failure.txt");
+const PARALLEL_VARIANTS: &str = include_str!("fixtures/msbuild_parallel_variants.txt");
+
+#[test]
+fn test_parse_cpp_failure() {
+    let actual = diagnostics::parse(CPP_FAILURE);
+    assert_eq!(
+        actual.len(),
+        6,
+        "The summary at the end of the build should not result in duplicates: \n {:?}",
+        actual
+    );
+    assert_eq!(
+        actual
+            .iter()
+            .filter(|d| d.severity() == Severity::Error)
+            .count(),
+        4
+    );
+
+    let project = Some(Path::new("C:\\src\\app\\core\\core.vcxproj"));
+    assert!(actual.iter().all(|d| d.project() == project));
+
+    let compile_error = &actual[0];
+    assert_eq!(compile_error.code(), Some("C2065"));
+    assert_eq!(
+        compile_error.file(),
+        Some(Path::new("C:\\src\\app\\core\\main.cpp"))
+    );
+    assert_eq!(compile_error.line(), Some(12));
+    assert_eq!(compile_error.column(), Some(5));
+    assert_eq!(compile_error.message(), "'value': undeclared identifier");
+
+    let fatal_error = &actual[2];
+    assert_eq!(fatal_error.severity(), Severity::Error);
+    assert_eq!(fatal_error.subcategory(), Some("fatal"));
+    assert_eq!(fatal_error.code(), Some("C1083"));
+    assert_eq!(fatal_error.line(), Some(1));
+    assert_eq!(fatal_error.column(), None);
+    assert_eq!(
+        fatal_error.message(),
+        "Cannot open include file: 'missing.h': No such file or directory"
+    );
+
+    let tool_warning = &actual[3];
+    assert_eq!(tool_warning.severity(), Severity::Warning);
+    assert_eq!(tool_warning.origin(), Some("cl"));
+    assert_eq!(tool_warning.file(), None);
+    assert_eq!(tool_warning.subcategory(), Some("command line"));
+    assert_eq!(tool_warning.code(), Some("D9025"));
+
+    let link_error = &actual[4];
+    assert_eq!(link_error.file(), Some(Path::new("main.obj")));
+    assert_eq!(link_error.line(), None);
+    assert_eq!(link_error.code(), Some("LNK2019"));
+
+    let link_fatal = &actual[5];
+    assert_eq!(link_fatal.origin(), Some("LINK"));
+    assert_eq!(link_fatal.file(), None);
+    assert_eq!(link_fatal.code(), Some("LNK1120"));
+}
+
+#[test]
+fn test_parse_parallel_variants() {
+    let actual = diagnostics::parse(PARALLEL_VARIANTS);
+    assert_eq!(actual.len(), 7, "Unexpected diagnostics: \n {:?}", actual);
+
+    let full_range = &actual[0];
+    assert_eq!(full_range.code(), Some("CS0168"));
+    assert_eq!(
+        full_range.file(),
+        Some(Path::new("C:\\src\\lib\\Parser.cs"))
+    );
+    assert_eq!(
+        (
+            full_range.line(),
+            full_range.column(),
+            full_range.end_line(),
+            full_range.end_column()
+        ),
+        (Some(10), Some(9), Some(10), Some(21))
+    );
+
+    let line_range = &actual[1];
+    assert_eq!(line_range.message(), "; expected");
+    assert_eq!(
+        (line_range.line(), line_range.end_line()),
+        (Some(20), Some(24))
+    );
+
+    let column_range = &actual[2];
+    assert_eq!(
+        (
+            column_range.line(),
+            column_range.column(),
+            column_range.end_column()
+        ),
+        (Some(31), Some(7), Some(15))
+    );
+
+    let no_code = &actual[3];
+    assert_eq!(no_code.code(), None);
+    assert_eq!(no_code.line(), Some(5));
+    assert_eq!(no_code.message(), "Missing XML comment");
+
+    let msbuild_error = &actual[4];
+    assert_eq!(msbuild_error.origin(), Some("MSBUILD"));
+    assert_eq!(msbuild_error.code(), Some("MSB1009"));
+    assert_eq!(msbuild_error.project(), None);
+
+    let no_origin = &actual[5];
+    assert_eq!(no_origin.origin(), None);
+    assert_eq!(no_origin.file(), None);
+    assert_eq!(no_origin.code(), Some("MSB4025"));
+
+    let path_with_spaces = &actual[6];
+    assert_eq!(path_with_spaces.file(), Some(Path::new("C:\\Program Files\\Microsoft Visual Studio\\2022\\Community\\MSBuild\\Microsoft\\VC\\v170\\Microsoft.CppBuild.targets")));
+    assert_eq!(path_with_spaces.code(), Some("MSB8028"));
+}
@@ -0,0 +1,117 @@
+use msbuild::{
+    CandidateStatus, InstallationCriteria, InstallationList, JsonFileSource, MsBuild,
+    RejectionReason, VsInstallation, VsProduct, VsProductLineVersion,
+};
+use std::path::Path;
+
+// Helper that returns the source for the recorded vswhere output.
+fn fixture_source() -> JsonFileSource {
+    JsonFileSource::new(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/vswhere_output.json"),
+    )
+}
+
+#[test]
+fn test_find_in_json_file() {
+    let source = fixture_source();
+
+    let installation = VsInstallation::find_in(&source, &InstallationCriteria::new())
+        .expect("It should be possible to find an installation in the fixture.");
+    assert_eq!(installation.instance_id(), Some("a1b2c3d4"));
+
+    let criteria = InstallationCriteria::new().prefer_products([VsProduct::BuildTools]);
+    let installation = VsInstallation::find_in(&source, &criteria)
+        .expect("It should be possible to find the preferred installation.");
+    assert_eq!(installation.instance_id(), Some("3f2b1c9a"));
+
+    let criteria = InstallationCriteria::new().version_range(
+        Some(VsProductLineVersion::Vs2019.installation_version_max()),
+        Some(VsProductLineVersion::Vs2019.installation_version_min()),
+    );
+    let error = VsInstallation::find_in(&source, &criteria)
+        .expect_err("The incomplete 2019 installation should not be selected.");
+    let report = error
+        .selection_report()
+        .expect("The error should contain a selection report.");
+    assert!(report
+        .candidates()
+        .iter()
+        .any(|c| c.instance_id() == Some("9e8d7c6b")
+            && *c.status() == CandidateStatus::Rejected(RejectionReason::Incomplete)));
+}
+
+#[test]
+fn test_explain_in_json_file() {
+    let report = VsInstallation::explain_in(&fixture_source(), &InstallationCriteria::new())
+        .expect("It should be possible to explain the selection.");
+    assert_eq!(report.candidates().len(), 4);
+    assert_eq!(
+        report.selected().and_then(|c| c.instance_id()),
+        Some("a1b2c3d4")
+    );
+    assert_eq!(report.rejected().count(), 2);
+}
+
+#[test]
+fn test_list_in() {
+    let installations = VsInstallation::list_in(&fixture_source())
+        .expect("It should be possible to list the installations.");
+    assert_eq!(
+        installations.len(),
+        3,
+        "The instance without an installation path should be skipped."
+    );
+
+    let error = VsInstallation::find_in(&InstallationList::default(), &InstallationCriteria::new())
+        .expect_err("An empty list should not contain any installation.");
+    assert_eq!(
+        error.selection_report().map(|r| r.candidates().len()),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_find_msbuild_in() {
+    let root = tempfile::tempdir().expect("It should be possible to create a temporary directory.");
+    let msbuild_path = root.path().join("MsBuild/Current/Bin/msbuild.exe");
+    std::fs::create_dir_all(msbuild_path.parent().unwrap())
+        .expect("It should be possible to create the directories.");
+    std::fs::write(&msbuild_path, "").expect("It should be possible to create the file.");
+    let vswhere_output = root.path().join("vswhere.json");
+    std::fs::write(
+        &vswhere_output,
+        serde_json::json!([{
+            "instanceId": "5a6b7c8d",
+            "installationPath": root.path(),
+            "installationVersion": "17.12.35527.113",
+            "productId": "Microsoft.VisualStudio.Product.BuildTools",
+        }])
+        .to_string(),
+    )
+    .expect("It should be possible to write the file.");
+
+    let source = JsonFileSource::new(vswhere_output);
+    let msbuild = MsBuild::find_msbuild_in(&source, &InstallationCriteria::new())
+        .expect("It should be possible to find msbuild in the installation.");
+    assert_eq!(msbuild.path(), msbuild_path.as_path());
+
+    let installations =
+        VsInstallation::list_in(&source).expect("It should be possible to list the installations.");
+    let list = InstallationList::from(installations);
+    let msbuild = MsBuild::find_msbuild_in(&list, &InstallationCriteria::new())
+        .expect("It should be possible to find msbuild from the listed installations.");
+    assert_eq!(msbuild.path(), msbuild_path.as_path());
+}
+
+#[test]
+fn test_capture_and_replay() {
+    let dir = tempfile::tempdir().expect("It should be possible to create a temporary directory.");
+    let capture = JsonFileSource::capture(&fixture_source(), dir.path().join("capture.json"))
+        .expect("It should be possible to capture the instances.");
+
+    let expected = VsInstallation::explain_in(&fixture_source(), &InstallationCriteria::new())
+        .expect("It should be possible to explain the selection.");
+    let actual = VsInstallation::explain_in(&capture, &InstallationCriteria::new())
+        .expect("It should be possible to explain the replayed selection.");
+    assert_eq!(actual.to_string(), expected.to_string());
+}
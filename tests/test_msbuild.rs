@@ -1,4 +1,4 @@
-use msbuild::{InstallationVersion, MsBuild, ProductLineVersion};
+use msbuild::{MsBuild, VsInstallationVersion, VsProductLineVersion};
 
 #[ignore]
 #[test]
@@ -21,8 +21,8 @@ fn test_find_msbuild_in_range_with_installed_version_in_range() {
     // is installed in the CI environment.
 
     assert!(MsBuild::find_msbuild_in_range(
-        Some(ProductLineVersion::Vs2022.installation_version_max()),
-        Some(ProductLineVersion::Vs2022.installation_version_min())
+        Some(VsProductLineVersion::Vs2022.installation_version_max()),
+        Some(VsProductLineVersion::Vs2022.installation_version_min())
     )
     .is_ok());
 }
@@ -30,13 +30,13 @@ fn test_find_msbuild_in_range_with_installed_version_in_range() {
 #[ignore]
 #[test]
 fn test_find_msbuild_with_installed_version_out_of_range() {
-    let invalid_min: InstallationVersion = InstallationVersion::parse("1000.0.0.0")
+    let invalid_min: VsInstallationVersion = VsInstallationVersion::parse("1000.0.0.0")
         .expect("Should be possible to parse valid version string");
 
     let invalid_min_result = MsBuild::find_msbuild_in_range(None, Some(invalid_min.clone()));
     assert!(invalid_min_result.is_err(), "Providing the function with a min version that would prevent it to find any products should result in an error.");
 
-    let invalid_max = InstallationVersion::parse("0.0.0.1")
+    let invalid_max = VsInstallationVersion::parse("0.0.0.1")
         .expect("Should be possible to parse valid version string");
     let invalid_max_result = MsBuild::find_msbuild_in_range(Some(invalid_max.clone()), None);
     assert!(invalid_max_result.is_err(), "Providing the function with a max version that would prevent it to find any products should result in an error.");
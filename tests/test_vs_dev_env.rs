@@ -0,0 +1,34 @@
+use msbuild::{Arch, HostTarget, InstallationCriteria, VsDevCmd, VsDevEnv, VsInstallation};
+
+#[ignore]
+#[test]
+fn test_computed_env_matches_vcvarsall() {
+    // Cannot run the test unless VS with the C++ tools and
+    // a Windows SDK have been installed into the test environment.
+
+    let installation = VsInstallation::find(&InstallationCriteria::new())
+        .expect("A VS installation should have been found if it was installed.");
+    let host_target = HostTarget::native(Arch::X64);
+    let computed = VsDevEnv::find(&installation, host_target)
+        .expect("It should be possible to compute the environment.");
+    let captured = VsDevCmd::vcvarsall(&installation, host_target)
+        .and_then(|vcvarsall| vcvarsall.run())
+        .expect("It should be possible to run vcvarsall.bat.");
+
+    ["VCToolsVersion", "UCRTVersion", "VSCMD_ARG_TGT_ARCH"]
+        .iter()
+        .for_each(|key| assert_eq!(computed.get(key), captured.get(key), "{}", key));
+    ["INCLUDE", "LIB"].iter().for_each(|key| {
+        let captured_paths = captured.paths(key);
+        computed.paths(key).iter().for_each(|path| {
+            assert!(
+                captured_paths.iter().any(|p| p
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(&path.to_string_lossy())),
+                "{} should contain {}",
+                key,
+                path.display()
+            )
+        })
+    });
+}